@@ -31,9 +31,18 @@ def main():
         bench = data_point['bench']
         variant = data_point['variant']
         assert variant in PROPERTIES
-        cycle_measurements = data_point['cycles']
-        assert len(set(cycle_measurements)) == 1, "TODO varying cycle counts??"
-        results[bench][variant] = cycle_measurements[0]
+        if data_point.get('panicked') or data_point.get('unsupported'):
+            # No cycle counts, see bench.py
+            continue
+        measurement = data_point.get('measurement')
+        if measurement is not None:
+            # Measured repeatedly inside one simulation, see bench.py
+            results[bench][variant] = measurement['median']
+        else:
+            # Older result files, or MEASURE_RUNS=0
+            cycle_measurements = data_point['cycles']
+            assert len(set(cycle_measurements)) == 1, "TODO varying cycle counts??"
+            results[bench][variant] = cycle_measurements[0]
         results[bench][variant + '_objsize'] = data_point['obj_size']
         results[bench][variant + '_exesize'] = data_point['exe_size']
 
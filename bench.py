@@ -36,21 +36,44 @@ def sh(cli, **kwargs):
         print(proc.stdout.decode('utf-8'))
     proc.check_returncode()
 
-def _build_rust_variant(bench, variant, features, rust_trig):
-    print("Building Rust benchmark:", bench, variant, "(rust trig)" if rust_trig else "")
+# Math backends for Rust benchmarks, as rust_nyuzi_staticlib features: None
+# (libm), 'math-rust', 'math-fast' or 'math-branchless'
+TRIG_SUFFIXES = {
+    'math-rust': '_vectrig',
+    'math-fast': '_fasttrig',
+    'math-branchless': '_branchlesstrig',
+}
+
+# Every benchmark and variant is built into one rust_nyuzi_staticlib with
+# `--cfg registry` (once per math backend and precision), the harness picks
+# the benchmark by name at runtime. Note that this makes obj_size the size
+# of the whole library for all Rust benchmarks.
+_rust_libs = {}
+
+def _build_rust_lib(trig, f64):
+    if (trig, f64) in _rust_libs:
+        return _rust_libs[trig, f64]
+    print("Building Rust benchmarks", "(" + trig + ")" if trig else "",
+          "(f64)" if f64 else "")
     os.chdir('rust_nyuzi_staticlib')
     env = dict(os.environ)
     assert 'RUSTFLAGS' not in env
-    env['RUSTFLAGS'] = '--cfg benchmark="{}" --cfg variant="{}"'.format(bench, variant)
-    if rust_trig:
-        env['RUSTFLAGS'] += ' --cfg rust_trig'
+    env['RUSTFLAGS'] = '--cfg registry'
+    if f64:
+        features = 'link_fib link_nbody_f64 link_fwt_f64'
+    else:
+        features = 'link_fib link_nbody link_fwt link_mandelbrot link_hash link_sgemm'
+    if trig:
+        features += ' ' + trig
     sh(['xargo', 'build', '--target=nyuzi-elf-none', '--release', '--features', features],
        env=env)
     CARGO_OUTPUT = 'target/nyuzi-elf-none/release/librust_nyuzi_staticlib.a'
-    archive = OUT_DIR / (bench  + '_' + variant + '.a')
+    suffix = TRIG_SUFFIXES.get(trig, '') + ('_f64' if f64 else '')
+    archive = OUT_DIR / ('rust' + suffix + '.a')
     shutil.copy(str(CARGO_OUTPUT), str(archive))
     os.chdir('..')
-    return _build_harness(bench, variant, archive, rust_trig=rust_trig)
+    _rust_libs[trig, f64] = archive
+    return archive
 
 def _build_cxx_variant(bench, variant, source_file, threads):
     defines = ['-DBENCH_' + bench.upper(), '-DVARIANT_' + variant.upper()]
@@ -64,23 +87,89 @@ def _build_cxx_variant(bench, variant, source_file, threads):
     sh([CLANG, source_file, *CXXFLAGS, *INCLUDES, *defines, '-c', '-o', obj])
     return _build_harness(bench, variant, obj, threads=threads)
 
-def _build_harness(bench, variant, bench_obj, *, threads=False, rust_trig=False):
+# Performance counter events to report, as two raw event numbers separated by
+# a comma (see nyuzi_support::perf), e.g. PERF_EVENTS=6,10. Counts are reported
+# for the whole benchmark, and for Rust benchmarks also per timed region.
+PERF_EVENTS = os.environ.get('PERF_EVENTS')
+
+# If set, Rust benchmarks also check that their scalar and SPMD variants give
+# the same outputs, with floats allowed to differ by this many ulp, e.g.
+# EQUIVALENCE_ULPS=4
+EQUIVALENCE_ULPS = os.environ.get('EQUIVALENCE_ULPS')
+
+# Rust benchmarks measure themselves inside one simulation: MEASURE_WARMUP
+# untimed runs, then MEASURE_RUNS timed ones, reported as minimum, median and
+# maximum cycles (see nyuzi_support::measure). MEASURE_RUNS=0 turns this off,
+# and the simulation is restarted for every measurement instead.
+MEASURE_WARMUP = int(os.environ.get('MEASURE_WARMUP', '2'))
+MEASURE_RUNS = int(os.environ.get('MEASURE_RUNS', '8'))
+
+# If set, Rust benchmarks are also run at the sizes in SIZE_SWEEPS, besides
+# their default size
+SIZE_SWEEP = os.environ.get('SIZE_SWEEP')
+
+# Problem sizes as (n, steps), see nyuzi_support::benchmark::Size. A zero
+# selects the benchmark's default.
+SIZE_SWEEPS = {
+    'fib_iter': [(64, 0), (256, 0), (1024, 0)],
+    'fib_rec': [(64, 0), (256, 0)],
+    'nbody': [(16, 0), (64, 0), (64, 4)],
+    'fwt': [(64, 0), (1024, 0), (4096, 0)],
+    'fwt_nodivmod': [(64, 0), (1024, 0), (4096, 0)],
+    'mandelbrot_rust': [(128, 0), (64, 1024)],
+    'hash_rust': [(64, 0), (256, 1)],
+    'sgemm_naive': [(32, 0), (128, 0), (256, 0)],
+    'sgemm_blocked': [(32, 0), (128, 0), (256, 0)],
+}
+
+def _build_harness(bench, variant, bench_obj, *, threads=False, trig=None, f64=False,
+                   registry=False, size=None):
     defines = ['-DBENCH_NAME=' + bench, '-DBENCH_VARIANT=' + variant]
+    if registry:
+        defines.append('-DBENCH_REGISTRY')
+        if MEASURE_RUNS:
+            defines += ['-DBENCH_WARMUP=' + str(MEASURE_WARMUP) + 'u',
+                        '-DBENCH_RUNS=' + str(MEASURE_RUNS) + 'u']
+    if size:
+        n, steps = size
+        defines += ['-DBENCH_SIZE=' + str(n) + 'u', '-DBENCH_STEPS=' + str(steps) + 'u']
+    if PERF_EVENTS:
+        event0, event1 = PERF_EVENTS.split(',')
+        defines += ['-DPERF_EVENT0=' + event0.strip(), '-DPERF_EVENT1=' + event1.strip()]
+    if EQUIVALENCE_ULPS:
+        defines.append('-DEQUIVALENCE_ULPS=' + str(int(EQUIVALENCE_ULPS)) + 'u')
     if threads:
         defines.append('-DUSE_THREADS')
         bench += '_threads'
-    if rust_trig:
-        bench += '_vectrig'
+    if trig:
+        bench += TRIG_SUFFIXES[trig]
+    if f64:
+        bench += '_f64'
+    if size:
+        n, steps = size
+        bench += '_n' + str(n)
+        if steps:
+            bench += '_s' + str(steps)
     elf_path = OUT_DIR / (bench + '_' + variant + '.elf')
     hex_path = elf_path.with_suffix('.hex')
     sh([CLANG, bench_obj, 'harness.cpp', *CXXFLAGS, *INCLUDES, *CRT, *defines,
         '-o', elf_path])
     sh([ELF2HEX, elf_path, '-o', hex_path])
-    return (bench, variant, hex_path, bench_obj, elf_path)
+    return (bench, variant, hex_path, bench_obj, elf_path, size)
 
-def build_rust(name, features, rust_trig=False):
-    yield _build_rust_variant(name, 'scalar', features, rust_trig)
-    yield _build_rust_variant(name, 'spmd', features, rust_trig)
+# `intrin` for benchmarks with a hand-vectorised variant, which only exists
+# in f32 builds
+def build_rust(name, trig=None, f64=False, intrin=False):
+    archive = _build_rust_lib(trig, f64)
+    sizes = [None]
+    if SIZE_SWEEP:
+        sizes += SIZE_SWEEPS.get(name, [])
+    variants = ('scalar', 'spmd', 'intrin') if intrin else ('scalar', 'spmd')
+    for size in sizes:
+        for variant in variants:
+            print("Linking Rust benchmark:", name, variant, *(size or ()))
+            yield _build_harness(name, variant, archive, trig=trig, f64=f64, registry=True,
+                                 size=size)
 
 def build_cxx(name, source_file):
     for variant in ('scalar', 'spmd', 'intrin'):
@@ -91,24 +180,98 @@ def build_all():
     return [
         *build_cxx('hash', 'hash/hash.cpp'),
         *build_cxx('mandelbrot', 'mandelbrot/mandelbrot.cpp'),
-        *build_rust('fib_iter', features='link_fib'),
-        *build_rust('fib_rec', features='link_fib'),
-        *build_rust('nbody', features='link_nbody'),
-        *build_rust('nbody', features='link_nbody', rust_trig=True),
-        *build_rust('fwt', features='link_fwt'),
-        *build_rust('fwt_nodivmod', features='link_fwt'),
+        *build_rust('fib_iter', intrin=True),
+        *build_rust('fib_rec', intrin=True),
+        *build_rust('nbody', intrin=True),
+        *build_rust('nbody', trig='math-rust', intrin=True),
+        *build_rust('nbody', trig='math-fast', intrin=True),
+        *build_rust('nbody', trig='math-branchless', intrin=True),
+        *build_rust('nbody', f64=True),
+        *build_rust('nbody', trig='math-rust', f64=True),
+        *build_rust('fwt', intrin=True),
+        *build_rust('fwt_nodivmod', intrin=True),
+        *build_rust('fwt', f64=True),
+        *build_rust('fwt_nodivmod', f64=True),
+        *build_rust('mandelbrot_rust'),
+        *build_rust('hash_rust'),
+        *build_rust('sgemm_naive'),
+        *build_rust('sgemm_blocked'),
     ]
 
+# Exit status of a Rust benchmark that panicked, see rust_nyuzi_staticlib
+PANIC_EXIT_STATUS = 101
+
+class BenchmarkPanicked(Exception):
+    pass
+
+class SizeUnsupported(Exception):
+    pass
+
 def run(hex_path):
     proc = subprocess.run(
         [str(VERILATOR), '+bin=' + str(hex_path), '+randseed=0x12345678'],
-        stdout=subprocess.PIPE, check=True,
+        stdout=subprocess.PIPE,
     )
-    prefix = 'elapsed:'
-    for line in proc.stdout.decode('utf-8').split('\n'):
-        if line.startswith(prefix):
-            return int(line[len(prefix):].strip())
-    raise Exception("did not find cycle count in harness output")
+    output = proc.stdout.decode('utf-8')
+    for line in output.split('\n'):
+        if line.startswith('panicked at'):
+            raise BenchmarkPanicked(line)
+        if line.startswith('unsupported size'):
+            raise SizeUnsupported(line)
+    if proc.returncode == PANIC_EXIT_STATUS:
+        raise BenchmarkPanicked("exited with status {}".format(PANIC_EXIT_STATUS))
+    proc.check_returncode()
+    elapsed = None
+    verified = None
+    equivalent = None
+    measurement = None
+    # Regions of the first run, those of the measured runs go into the
+    # measurement
+    run_regions = regions = {}
+    run_region_perf = region_perf = {}
+    region_overflow = 0
+    perf = {}
+    for line in output.split('\n'):
+        if line.startswith('elapsed:'):
+            elapsed = int(line[len('elapsed:'):].strip())
+        elif line.startswith('verify:'):
+            # verify:pass or verify:fail, only for Rust benchmarks. Printed
+            # again after the measurement, all of them have to pass.
+            passed = line[len('verify:'):].strip() == 'pass'
+            verified = passed and verified is not False
+        elif line.startswith('equivalence:'):
+            # equivalence:PASS or equivalence:FAIL, see EQUIVALENCE_ULPS
+            equivalent = line[len('equivalence:'):].strip() == 'PASS'
+        elif line.startswith('measure:'):
+            # measure:<name>:<variant>:<warmup>:<runs>:<min>:<median>:<max>,
+            # see MEASURE_RUNS
+            fields = line[len('measure:'):].strip().split(':')
+            warmup, runs, min_cycles, median, max_cycles = map(int, fields[2:])
+            regions, region_perf = {}, {}
+            measurement = {'warmup': warmup, 'runs': runs, 'min': min_cycles,
+                           'median': median, 'max': max_cycles,
+                           'regions': regions, 'region_perf': region_perf}
+        elif line.startswith('region:'):
+            # region:<name>:<cycles>, written by nyuzi_support::timer
+            name, cycles = line[len('region:'):].rsplit(':', 1)
+            regions[name] = int(cycles)
+        elif line.startswith('region_perf:'):
+            # region_perf:<name>:<event>:<count>, written by nyuzi_support::timer
+            # if PERF_EVENTS is set
+            name, event, count = line[len('region_perf:'):].rsplit(':', 2)
+            region_perf.setdefault(name, {})[event] = int(count)
+        elif line.startswith('region_overflow:'):
+            # region_overflow:<runs>, the runs of regions nyuzi_support::timer
+            # had no room left for, so the regions above are incomplete
+            region_overflow += int(line[len('region_overflow:'):].strip())
+        elif line.startswith('perf:'):
+            # perf:<event>:<count>, see PERF_EVENTS
+            event, count = line[len('perf:'):].split(':')
+            perf[event] = int(count)
+    if elapsed is None:
+        raise Exception("did not find cycle count in harness output")
+    return (elapsed, verified, equivalent, measurement, run_regions, run_region_perf,
+            region_overflow, perf)
 
 def main():
     assert Path.cwd() == Path(__file__).resolve().parent
@@ -116,18 +279,72 @@ def main():
     OUT_DIR.mkdir()
     benchmarks = build_all()
     results = []
-    for (bench, variant, hex_path, obj_path, elf_path) in benchmarks:
+    for (bench, variant, hex_path, obj_path, elf_path, size) in benchmarks:
         print("Running:", bench, variant)
         BENCH_RUNS = 3
         cycles_measurements = []
-        for _ in range(BENCH_RUNS):
-            cycles_measurements.append(run(hex_path))
+        region_measurements = {}
+        region_perf_measurements = {}
+        perf_measurements = {}
+        region_overflow = 0
+        verified = None
+        equivalent = None
+        measurement = None
+        panicked = None
+        unsupported = None
+        try:
+            for _ in range(BENCH_RUNS):
+                (cycles, run_verified, run_equivalent, measurement, regions,
+                 region_perf, run_region_overflow, perf) = run(hex_path)
+                cycles_measurements.append(cycles)
+                if run_verified is not None:
+                    verified = run_verified and verified is not False
+                if run_equivalent is not None:
+                    equivalent = run_equivalent and equivalent is not False
+                for name, region_cycles in regions.items():
+                    region_measurements.setdefault(name, []).append(region_cycles)
+                for name, counts in region_perf.items():
+                    for event, count in counts.items():
+                        (region_perf_measurements.setdefault(name, {})
+                         .setdefault(event, []).append(count))
+                region_overflow += run_region_overflow
+                for event, count in perf.items():
+                    perf_measurements.setdefault(event, []).append(count)
+                if measurement is not None:
+                    # Already measured repeatedly inside the simulation
+                    break
+        # Both are recorded in the results as well, marked as such and not
+        # verified, instead of silently leaving the benchmark out
+        except BenchmarkPanicked as e:
+            print("PANICKED:", e)
+            panicked = str(e)
+            verified = False
+        except SizeUnsupported as e:
+            print("SKIPPED:", e)
+            unsupported = str(e)
+            verified = False
+        if verified is False and panicked is None and unsupported is None:
+            print("WRONG RESULTS:", bench, variant)
+        if equivalent is False:
+            print("SCALAR AND SPMD DIFFER:", bench)
+        if region_overflow:
+            print("REGIONS DROPPED:", bench, variant, region_overflow)
         obj_size = obj_path.stat().st_size
         exe_size = elf_path.stat().st_size
         results.append({
             'bench': bench,
             'variant': variant,
+            'size': list(size) if size else None,
             'cycles': cycles_measurements,
+            'verified': verified,
+            'panicked': panicked,
+            'unsupported': unsupported,
+            'equivalent': equivalent,
+            'measurement': measurement,
+            'regions': region_measurements,
+            'region_perf': region_perf_measurements,
+            'region_overflow': region_overflow,
+            'perf': perf_measurements,
             'obj_size': obj_size,
             'exe_size': exe_size
         })
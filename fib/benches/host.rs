@@ -0,0 +1,14 @@
+// Wall-clock times of all variants of every benchmark on the host, see
+// tests/host.rs.
+#![feature(test)]
+extern crate test;
+#[macro_use]
+extern crate nyuzi_support;
+extern crate fib;
+
+use fib::Fib;
+
+host_benches! {
+    fib_iter => Fib::iterative(), intrin;
+    fib_rec => Fib::recursive(), intrin;
+}
@@ -3,8 +3,13 @@
 
 #![no_std]
 #![allow(dead_code)]
+#[macro_use]
 extern crate nyuzi_support;
 
+use nyuzi_support::benchmark::{Benchmark, IntrinBenchmark, Size};
+use nyuzi_support::intrin::{I32x16, LANES, Mask};
+use nyuzi_support::pool;
+
 fn fib_rec(n: i32) -> i32 {
     if n < 2 {
         1
@@ -24,28 +29,122 @@ fn fib_iter(n: i32) -> i32 {
     a
 }
 
+/// fib_rec of every lane where `active` is set. The recursion continues as
+/// long as any lane needs it, the others are masked off.
+fn fib_rec_vector(n: I32x16, active: Mask) -> I32x16 {
+    let one = I32x16::splat(1);
+    let recurse = active & n.ge(I32x16::splat(2));
+    if recurse.none() {
+        return one;
+    }
+    let sum = fib_rec_vector(n - one, recurse) + fib_rec_vector(n - I32x16::splat(2), recurse);
+    recurse.select(sum, one)
+}
+
+/// fib_iter of every lane, iterating until the largest one is done.
+fn fib_iter_vector(n: I32x16) -> I32x16 {
+    let mut a = I32x16::splat(0);
+    let mut b = I32x16::splat(1);
+    let mut i = 0;
+    loop {
+        let active = I32x16::splat(i).lt(n);
+        if active.none() {
+            return a;
+        }
+        let c = a + b;
+        a = active.select(b, a);
+        b = active.select(c, b);
+        i += 1;
+    }
+}
+
+// The default input, `Size::n` inputs repeat it
 const INPUT: [i32; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
 
-#[no_mangle]
-#[cfg(all(benchmark="fib_iter", variant="scalar"))]
-pub extern "C" fn fib_iter_scalar() {
-    nyuzi_support::run_scalar(&mut INPUT, |x| *x = fib_iter(*x));
+// The Fibonacci numbers, fib_iter(n) is FIBONACCI[n] and fib_rec(n) is
+// FIBONACCI[n + 1]
+const FIBONACCI: [i32; 17] = [0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 377, 610, 987];
+
+pub struct Fib {
+    recursive: bool,
+    data: &'static mut [i32],
+}
+
+impl Fib {
+    pub fn iterative() -> Self {
+        Fib { recursive: false, data: &mut [] }
+    }
+
+    pub fn recursive() -> Self {
+        Fib { recursive: true, data: &mut [] }
+    }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fib_iter", variant="spmd"))]
-pub extern "C" fn fib_iter_spmd() {
-    nyuzi_support::run_vector(&mut INPUT, |x| *x = fib_iter(*x));
+impl Benchmark for Fib {
+    type Output = i32;
+
+    /// `size.n` must be a multiple of 16 (the default), there are no steps.
+    fn setup(&mut self, size: Size) -> bool {
+        let n = if size.n == 0 { INPUT.len() } else { size.n };
+        if n % 16 != 0 || size.steps != 0 {
+            return false;
+        }
+        match pool::alloc(n, 0) {
+            Some(data) => self.data = data,
+            None => return false,
+        }
+        for (x, &input) in self.data.iter_mut().zip(INPUT.iter().cycle()) {
+            *x = input;
+        }
+        true
+    }
+
+    fn run_scalar(&mut self) {
+        if self.recursive {
+            nyuzi_support::run_scalar(self.data, |x| *x = fib_rec(*x));
+        } else {
+            nyuzi_support::run_scalar(self.data, |x| *x = fib_iter(*x));
+        }
+    }
+
+    fn run_spmd(&mut self) {
+        if self.recursive {
+            nyuzi_support::run_vector(self.data, |x| *x = fib_rec(*x));
+        } else {
+            nyuzi_support::run_vector(self.data, |x| *x = fib_iter(*x));
+        }
+    }
+
+    fn verify(&self) -> bool {
+        let offset = if self.recursive { 1 } else { 0 };
+        self.data
+            .iter()
+            .zip(INPUT.iter().cycle())
+            .all(|(&out, &n)| out == FIBONACCI[n as usize + offset])
+    }
+
+    fn output(&self) -> &[i32] {
+        self.data
+    }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fib_rec", variant="scalar"))]
-pub extern "C" fn fib_rec_scalar() {
-    nyuzi_support::run_scalar(&mut INPUT, |x| *x = fib_rec(*x));
+impl IntrinBenchmark for Fib {
+    fn run_intrin(&mut self) {
+        for chunk in 0..self.data.len() / LANES {
+            let start = chunk * LANES;
+            let n = I32x16::load(self.data, start);
+            let fib = if self.recursive {
+                fib_rec_vector(n, Mask::full())
+            } else {
+                fib_iter_vector(n)
+            };
+            fib.store(self.data, start);
+        }
+    }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fib_rec", variant="spmd"))]
-pub extern "C" fn fib_rec_spmd() {
-    nyuzi_support::run_vector(&mut INPUT, |x| *x = fib_rec(*x));
+bench! {
+    "fib_iter" => static FIB_ITER: Fib = Fib { recursive: false, data: &mut [] }, intrin;
+
+    "fib_rec" => static FIB_REC: Fib = Fib { recursive: true, data: &mut [] }, intrin;
 }
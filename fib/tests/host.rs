@@ -0,0 +1,12 @@
+// All variants of every benchmark, run on the host through the fallbacks of
+// the SPMD functions and the host versions of the `intrin` types.
+#[macro_use]
+extern crate nyuzi_support;
+extern crate fib;
+
+use fib::Fib;
+
+host_tests! {
+    fib_iter => Fib::iterative(), sizes: [32, 256], intrin;
+    fib_rec => Fib::recursive(), sizes: [32, 256], intrin;
+}
@@ -0,0 +1,21 @@
+// Wall-clock times of all variants of every benchmark on the host, see
+// tests/host.rs.
+#![feature(test)]
+extern crate test;
+#[macro_use]
+extern crate nyuzi_support;
+extern crate fwt;
+
+use fwt::Fwt;
+
+#[cfg(not(feature = "f64"))]
+host_benches! {
+    fwt_divmod => Fwt::new(), intrin;
+    fwt_nodivmod => Fwt::nodivmod(), intrin;
+}
+
+#[cfg(feature = "f64")]
+host_benches! {
+    fwt_divmod => Fwt::new();
+    fwt_nodivmod => Fwt::nodivmod();
+}
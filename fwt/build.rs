@@ -10,6 +10,20 @@ const LENGTH: usize = 512;
 
 const SEED: [u32; 4] = [1, 2, 3, 4];
 
+/// The Walsh transform straight from its definition, in f64:
+/// output[k] is the sum of input[n] * (-1)^popcount(n & k).
+fn walsh_transform(input: &[f32]) -> Vec<f64> {
+    (0..input.len())
+        .map(|k| {
+            input
+                .iter()
+                .enumerate()
+                .map(|(n, &x)| if (n & k).count_ones() % 2 == 0 { x as f64 } else { -x as f64 })
+                .sum()
+        })
+        .collect()
+}
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("input.rs");
@@ -19,7 +33,11 @@ fn main() {
     let input: Vec<f32> = (0..LENGTH).map(|_| rng.gen_range(0.0, 255.0)).collect();
     write!(&mut f, "const LENGTH: usize = {};", LENGTH).unwrap();
     write!(&mut f,
-           "static mut INPUT_INIT: [f32; LENGTH] = {:?};",
+           "static mut INPUT_INIT: [Float; LENGTH] = {:?};",
            input)
             .unwrap();
+    write!(&mut f,
+           "static OUTPUT_REFERENCE: [Float; LENGTH] = {:?};",
+           walsh_transform(&input))
+            .unwrap();
 }
@@ -17,13 +17,27 @@ THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
 
 #![no_std]
 #![allow(dead_code, unused_imports)]
+#[macro_use]
 extern crate nyuzi_support;
 use core::cell::Cell;
 use core::mem::transmute;
 use nyuzi_support::spmd_range;
+#[cfg(not(feature = "f64"))]
+use nyuzi_support::benchmark::IntrinBenchmark;
+use nyuzi_support::benchmark::{Benchmark, Size};
+#[cfg(not(feature = "f64"))]
+use nyuzi_support::intrin::{F32x16, LANES, U32x16};
+use nyuzi_support::pool;
+use nyuzi_support::timer::Region;
+
+// The `f64` feature runs the transform in double precision
+#[cfg(not(feature = "f64"))]
+type Float = f32;
+#[cfg(feature = "f64")]
+type Float = f64;
 
 /* tid = get_global_id(0) */
-fn fwt_kernel(xs: &[Cell<f32>], step: usize, tid: usize) {
+fn fwt_kernel(xs: &[Cell<Float>], step: usize, tid: usize) {
     let group = tid % step;
     let pair = 2 * step * (tid / step) + group;
 
@@ -39,7 +53,7 @@ fn fwt_kernel(xs: &[Cell<f32>], step: usize, tid: usize) {
 }
 
 /* tid = get_global_id(0) */
-fn fwt_nodivmod_kernel(xs: &[Cell<f32>], step: usize, step_log2: usize, tid: usize) {
+fn fwt_nodivmod_kernel(xs: &[Cell<Float>], step: usize, step_log2: usize, tid: usize) {
     let group = tid & (step - 1);
     let pair = 2 * step * (tid >> step_log2) + group;
 
@@ -54,68 +68,209 @@ fn fwt_nodivmod_kernel(xs: &[Cell<f32>], step: usize, step_log2: usize, tid: usi
     }
 }
 
+/// The butterflies of the kernels for 16 consecutive `tid`s. There are no
+/// vectors of f64, so the `f64` feature has no intrin variant.
+#[cfg(not(feature = "f64"))]
+fn fwt_intrin_kernel(xs: &mut [f32], step: usize, step_log2: usize, nodivmod: bool, tid: usize) {
+    let tid = U32x16::splat(tid as u32) + U32x16::lane_ids();
+    let (group, pair) = if nodivmod {
+        (tid & U32x16::splat(step as u32 - 1), tid >> step_log2 as u32)
+    } else {
+        (tid % U32x16::splat(step as u32), tid / U32x16::splat(step as u32))
+    };
+    let pair = U32x16::splat(2 * step as u32) * pair + group;
 
-include!(concat!(env!("OUT_DIR"), "/input.rs"));
+    let partner = pair + U32x16::splat(step as u32);
 
-static mut INPUT: [f32; LENGTH] = [0.0; LENGTH];
+    unsafe {
+        let t1 = F32x16::gather_unchecked(xs.as_ptr(), pair);
+        let t2 = F32x16::gather_unchecked(xs.as_ptr(), partner);
 
-unsafe fn get_data() -> &'static [Cell<f32>] {
-    INPUT.copy_from_slice(&INPUT_INIT);
-    &*(&mut INPUT as *mut [f32] as *const [Cell<f32>])
+        (t1 + t2).scatter_unchecked(xs.as_mut_ptr(), pair);
+        (t1 - t2).scatter_unchecked(xs.as_mut_ptr(), partner);
+    }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fwt", variant="scalar"))]
-pub extern fn fwt_scalar() {
-    let xs = unsafe { get_data() };
+include!(concat!(env!("OUT_DIR"), "/input.rs"));
+
+// Tolerance of `verify`, relative to the largest expected value. The kernels
+// round in each of the log2(n) stages, OUTPUT_REFERENCE is exact up to f64
+// rounding.
+const TOLERANCE: Float = 1e-5;
+
+/// The transform of `xs`, in place, one butterfly at a time. Applying it
+/// twice gives the input times `xs.len()`, which `verify` relies on for sizes
+/// without a reference output.
+fn transform(xs: &mut [Float]) {
     let mut step = 1;
     while step < xs.len() {
-        for tid in 0..(xs.len() / 2) {
-            fwt_kernel(xs, step, tid);
+        for group in 0..xs.len() / (2 * step) {
+            for pair in group * 2 * step..group * 2 * step + step {
+                let (t1, t2) = (xs[pair], xs[pair + step]);
+                xs[pair] = t1 + t2;
+                xs[pair + step] = t1 - t2;
+            }
         }
         step <<= 1;
     }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fwt", variant="spmd"))]
-pub extern fn fwt_spmd() {
-    let xs = unsafe { get_data() };
-    let mut step = 1;
-    while step < xs.len() {
-        spmd_range(0..xs.len() / 2, |tid: usize| {
-            fwt_kernel(xs, step, tid);
-        });
-        step <<= 1;
+fn max_abs<I: Iterator<Item = Float>>(xs: I) -> Float {
+    xs.fold(0.0, |m, x| {
+        if x > m {
+            x
+        } else if -x > m {
+            -x
+        } else {
+            m
+        }
+    })
+}
+
+/// Whether `actual` and `expected` differ by at most `TOLERANCE * scale`.
+/// False for NaNs.
+fn close<I: Iterator<Item = Float>>(actual: &[Float], expected: I, scale: Float) -> bool {
+    let tolerance = scale * TOLERANCE;
+    actual.iter().zip(expected).all(|(&a, e)| a - e <= tolerance && e - a <= tolerance)
+}
+
+fn input() -> &'static [Float] {
+    unsafe { &INPUT_INIT }
+}
+
+pub struct Fwt {
+    nodivmod: bool,
+    data: &'static mut [Float],
+}
+
+impl Fwt {
+    pub fn new() -> Self {
+        Fwt { nodivmod: false, data: &mut [] }
+    }
+
+    pub fn nodivmod() -> Self {
+        Fwt { nodivmod: true, data: &mut [] }
+    }
+
+    fn cells(&mut self) -> &[Cell<Float>] {
+        unsafe { &*(&mut *self.data as *mut [Float] as *const [Cell<Float>]) }
     }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fwt_nodivmod", variant="scalar"))]
-pub extern fn fwt_nodivmod_scalar() {
-    let xs = unsafe { get_data() };
-    let mut step = 1;
-    let mut step_log2 = 1;
-    while step < xs.len() {
-        for tid in 0..(xs.len() / 2) {
-            fwt_nodivmod_kernel(xs, step, step_log2, tid);
+impl Benchmark for Fwt {
+    type Output = Float;
+
+    /// `size.n` is the length, a power of two of at least 32 (the default is
+    /// LENGTH). Longer inputs repeat the default one. There are no steps.
+    fn setup(&mut self, size: Size) -> bool {
+        let n = if size.n == 0 { LENGTH } else { size.n };
+        if !n.is_power_of_two() || n < 32 || size.steps != 0 {
+            return false;
         }
-        step <<= 1;
-        step_log2 += 1;
+        match pool::alloc(n, 0.0) {
+            Some(data) => self.data = data,
+            None => return false,
+        }
+        for (x, &input) in self.data.iter_mut().zip(input().iter().cycle()) {
+            *x = input;
+        }
+        true
+    }
+
+    fn run_scalar(&mut self) {
+        let nodivmod = self.nodivmod;
+        let xs = self.cells();
+        let mut step = 1;
+        let mut step_log2 = 0;
+        while step < xs.len() {
+            let _stage = Region::indexed("stage", step_log2);
+            if nodivmod {
+                for tid in 0..(xs.len() / 2) {
+                    fwt_nodivmod_kernel(xs, step, step_log2, tid);
+                }
+            } else {
+                for tid in 0..(xs.len() / 2) {
+                    fwt_kernel(xs, step, tid);
+                }
+            }
+            step <<= 1;
+            step_log2 += 1;
+        }
+    }
+
+    fn run_spmd(&mut self) {
+        let nodivmod = self.nodivmod;
+        let xs = self.cells();
+        let mut step = 1;
+        let mut step_log2 = 0;
+        while step < xs.len() {
+            let _stage = Region::indexed("stage", step_log2);
+            if nodivmod {
+                spmd_range(0..xs.len() / 2, |tid: usize| {
+                    fwt_nodivmod_kernel(xs, step, step_log2, tid);
+                });
+            } else {
+                spmd_range(0..xs.len() / 2, |tid: usize| {
+                    fwt_kernel(xs, step, tid);
+                });
+            }
+            step <<= 1;
+            step_log2 += 1;
+        }
+    }
+
+    fn verify(&self) -> bool {
+        if self.data.len() == LENGTH {
+            let scale = max_abs(OUTPUT_REFERENCE.iter().cloned());
+            return close(self.data, OUTPUT_REFERENCE.iter().cloned(), scale);
+        }
+
+        let n = self.data.len();
+        let back = match pool::alloc(n, 0.0) {
+            Some(back) => back,
+            None => return false,
+        };
+        back.copy_from_slice(self.data);
+        transform(back);
+        let expected = input().iter().cycle().map(|&x| x * n as Float);
+        let scale = max_abs(input().iter().take(n).cloned()) * n as Float;
+        close(back, expected, scale)
+    }
+
+    fn output(&self) -> &[Float] {
+        self.data
     }
 }
 
-#[no_mangle]
-#[cfg(all(benchmark="fwt_nodivmod", variant="spmd"))]
-pub extern fn fwt_nodivmod_spmd() {
-    let xs = unsafe { get_data() };
-    let mut step = 1;
-    let mut step_log2 = 0;
-    while step < xs.len() {
-        spmd_range(0..xs.len() / 2, |tid: usize| {
-            fwt_nodivmod_kernel(xs, step, step_log2, tid);
-        });
-        step <<= 1;
-        step_log2 += 1;
+#[cfg(not(feature = "f64"))]
+impl IntrinBenchmark for Fwt {
+    fn run_intrin(&mut self) {
+        let nodivmod = self.nodivmod;
+        let xs = &mut *self.data;
+        let mut step = 1;
+        let mut step_log2 = 0;
+        while step < xs.len() {
+            let _stage = Region::indexed("stage", step_log2);
+            // setup guarantees at least 32 elements, so whole vectors of tids
+            for chunk in 0..xs.len() / 2 / LANES {
+                fwt_intrin_kernel(xs, step, step_log2, nodivmod, chunk * LANES);
+            }
+            step <<= 1;
+            step_log2 += 1;
+        }
     }
 }
+
+#[cfg(not(feature = "f64"))]
+bench! {
+    "fwt" => static FWT: Fwt = Fwt { nodivmod: false, data: &mut [] }, intrin;
+
+    "fwt_nodivmod" => static FWT_NODIVMOD: Fwt = Fwt { nodivmod: true, data: &mut [] }, intrin;
+}
+
+#[cfg(feature = "f64")]
+bench! {
+    "fwt" => static FWT: Fwt = Fwt { nodivmod: false, data: &mut [] };
+
+    "fwt_nodivmod" => static FWT_NODIVMOD: Fwt = Fwt { nodivmod: true, data: &mut [] };
+}
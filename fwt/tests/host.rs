@@ -0,0 +1,19 @@
+// All variants of every benchmark, run on the host through the fallbacks of
+// the SPMD functions and the host versions of the `intrin` types.
+#[macro_use]
+extern crate nyuzi_support;
+extern crate fwt;
+
+use fwt::Fwt;
+
+#[cfg(not(feature = "f64"))]
+host_tests! {
+    fwt_divmod => Fwt::new(), sizes: [32, 2048], intrin;
+    fwt_nodivmod => Fwt::nodivmod(), sizes: [32, 2048], intrin;
+}
+
+#[cfg(feature = "f64")]
+host_tests! {
+    fwt_divmod => Fwt::new(), sizes: [32, 2048];
+    fwt_nodivmod => Fwt::nodivmod(), sizes: [32, 2048];
+}
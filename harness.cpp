@@ -8,15 +8,147 @@
 #define CONCAT_(x, y) x ## _ ## y
 #define CONCAT(x, y) CONCAT_(x, y)
 #define BENCH_FUNC CONCAT(BENCH_NAME, BENCH_VARIANT)
+#define BENCH_SETUP CONCAT(BENCH_NAME, setup)
+#define BENCH_VERIFY CONCAT(BENCH_NAME, verify)
+#define BENCH_EQUIVALENT CONCAT(BENCH_NAME, equivalent)
+
+// With BENCH_REGISTRY, the benchmark is looked up by name in the table
+// exported by a rust_nyuzi_staticlib built with `--cfg registry`, instead of
+// being linked by symbol name.
+#ifdef BENCH_REGISTRY
+#define STRINGIFY_(x) #x
+#define STRINGIFY(x) STRINGIFY_(x)
+#endif
 
 extern "C" {
+#ifdef BENCH_REGISTRY
+  int nyuzi_registry_find(const char *name, const char *variant);
+  bool nyuzi_registry_setup(unsigned int index, unsigned int n, unsigned int steps);
+  bool nyuzi_registry_run(unsigned int index);
+  bool nyuzi_registry_verify(unsigned int index);
+  bool nyuzi_registry_equivalent(unsigned int index, unsigned int n, unsigned int steps,
+                                 unsigned int max_ulps);
+  bool nyuzi_registry_measure(unsigned int index, unsigned int n, unsigned int steps,
+                              unsigned int warmup, unsigned int runs);
+#else
   void BENCH_FUNC();
+  // Rust benchmarks set up their inputs and check their outputs outside the
+  // timed region (see nyuzi_support::benchmark), C++ ones have neither.
+  bool BENCH_SETUP(unsigned int n, unsigned int steps) __attribute__((weak));
+  bool BENCH_VERIFY() __attribute__((weak));
+  bool BENCH_EQUIVALENT(unsigned int n, unsigned int steps, unsigned int max_ulps)
+    __attribute__((weak));
+#endif
+  // Provided by nyuzi_support when a Rust benchmark is linked in
+  void nyuzi_timer_report() __attribute__((weak));
+  void nyuzi_perf_configure(unsigned int event0, unsigned int event1) __attribute__((weak));
 }
 
+// PERF_EVENT0 and PERF_EVENT1 select hardware performance counter events
+// (raw numbers, see nyuzi_support::perf), whose counts are reported along
+// with the elapsed cycles. Like the cycle count, only the low 32 bits are used.
+// Rust benchmarks configure the counters through nyuzi_support::perf, so that
+// their timed regions report the counts as well.
+#if defined(PERF_EVENT0) && defined(PERF_EVENT1)
+#define USE_PERF_COUNTERS
+#endif
+
+// BENCH_SIZE and BENCH_STEPS set the problem size of a Rust benchmark, see
+// nyuzi_support::benchmark::Size. Zero (the default) selects the benchmark's
+// default size.
+#ifndef BENCH_SIZE
+#define BENCH_SIZE 0
+#endif
+#ifndef BENCH_STEPS
+#define BENCH_STEPS 0
+#endif
+
+// With BENCH_RUNS (registry builds only), a Rust benchmark is additionally
+// run BENCH_WARMUP times untimed and then BENCH_RUNS times timed, and the
+// minimum, median and maximum cycle counts are printed as one line (see
+// nyuzi_support::measure). The outputs of the last run are verified again,
+// and the timed regions of these runs are reported after that line.
+#if defined(BENCH_RUNS) && !defined(BENCH_WARMUP)
+#define BENCH_WARMUP 0
+#endif
+
+// With EQUIVALENCE_ULPS, a Rust benchmark additionally runs its scalar, SPMD
+// and (if it has one) intrin variants on the same inputs after the
+// measurement, and reports whether the outputs match (floats to within
+// EQUIVALENCE_ULPS ulp).
+
 int main() {
+#ifdef BENCH_REGISTRY
+  int bench_index = nyuzi_registry_find(STRINGIFY(BENCH_NAME), STRINGIFY(BENCH_VARIANT));
+  if (bench_index < 0) {
+    printf("unknown benchmark %s %s\n", STRINGIFY(BENCH_NAME), STRINGIFY(BENCH_VARIANT));
+    return 1;
+  }
+  bool supported = nyuzi_registry_setup(bench_index, BENCH_SIZE, BENCH_STEPS);
+#else
+  bool supported = !BENCH_SETUP || BENCH_SETUP(BENCH_SIZE, BENCH_STEPS);
+#endif
+  if (!supported) {
+    printf("unsupported size %u %u\n", BENCH_SIZE, BENCH_STEPS);
+    return 1;
+  }
+#ifdef USE_PERF_COUNTERS
+  if (nyuzi_perf_configure) {
+    nyuzi_perf_configure(PERF_EVENT0, PERF_EVENT1);
+  } else {
+    __builtin_nyuzi_write_control_reg(22, PERF_EVENT0);
+    __builtin_nyuzi_write_control_reg(23, PERF_EVENT1);
+  }
+  unsigned int perf0 = __builtin_nyuzi_read_control_reg(24);
+  unsigned int perf1 = __builtin_nyuzi_read_control_reg(26);
+#endif
   int t0 = __builtin_nyuzi_read_control_reg(6);
+#ifdef BENCH_REGISTRY
+  nyuzi_registry_run(bench_index);
+#else
   BENCH_FUNC();
+#endif
   int elapsed = __builtin_nyuzi_read_control_reg(6) - t0;
+#ifdef USE_PERF_COUNTERS
+  perf0 = __builtin_nyuzi_read_control_reg(24) - perf0;
+  perf1 = __builtin_nyuzi_read_control_reg(26) - perf1;
+#endif
   printf("elapsed:%d\n", elapsed);
+#ifdef BENCH_REGISTRY
+  printf("verify:%s\n", nyuzi_registry_verify(bench_index) ? "pass" : "fail");
+#else
+  if (BENCH_VERIFY)
+    printf("verify:%s\n", BENCH_VERIFY() ? "pass" : "fail");
+#endif
+#ifdef USE_PERF_COUNTERS
+  printf("perf:%d:%u\n", PERF_EVENT0, perf0);
+  printf("perf:%d:%u\n", PERF_EVENT1, perf1);
+#endif
+  if (nyuzi_timer_report)
+    nyuzi_timer_report();
+#if defined(BENCH_REGISTRY) && defined(BENCH_RUNS)
+  if (!nyuzi_registry_measure(bench_index, BENCH_SIZE, BENCH_STEPS, BENCH_WARMUP, BENCH_RUNS)) {
+    printf("measurement failed\n");
+    return 1;
+  }
+  // Checks the outputs of the last run, and reports the regions added up over
+  // all the measured runs (the report above cleared those of the first run)
+  printf("verify:%s\n", nyuzi_registry_verify(bench_index) ? "pass" : "fail");
+  if (nyuzi_timer_report)
+    nyuzi_timer_report();
+#endif
+#ifdef EQUIVALENCE_ULPS
+#ifdef BENCH_REGISTRY
+  bool equivalent = nyuzi_registry_equivalent(bench_index, BENCH_SIZE, BENCH_STEPS,
+                                              EQUIVALENCE_ULPS);
+  printf("equivalence:%s\n", equivalent ? "PASS" : "FAIL");
+#else
+  if (BENCH_EQUIVALENT)
+  {
+    bool equivalent = BENCH_EQUIVALENT(BENCH_SIZE, BENCH_STEPS, EQUIVALENCE_ULPS);
+    printf("equivalence:%s\n", equivalent ? "PASS" : "FAIL");
+  }
+#endif
+#endif
   return 0;
 }
@@ -0,0 +1,13 @@
+// Wall-clock times of both variants of the benchmark on the host, see
+// tests/host.rs.
+#![feature(test)]
+extern crate test;
+#[macro_use]
+extern crate nyuzi_support;
+extern crate hash;
+
+use hash::Hash;
+
+host_benches! {
+    double_sha256 => Hash::new();
+}
@@ -0,0 +1,273 @@
+// Port of hash/hash.cpp (Copyright 2011-2015 Jeff Bush, Apache License 2.0),
+// a rough simulation of Bitcoin hashing: double SHA-256 of a message per
+// lane, all integer operations. Unlike hash.cpp, the hashes are correct and
+// complete (with padding and length), so they can be checked against known
+// values.
+//
+// The benchmark is exported as `hash_rust`, to keep it apart from the C++ one
+// in the results.
+
+#![no_std]
+#![allow(dead_code)]
+#[macro_use]
+extern crate nyuzi_support;
+
+use nyuzi_support::benchmark::{Benchmark, Equivalent, Size};
+use nyuzi_support::pool;
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1,
+    0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
+    0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+    0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147,
+    0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+    0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+    0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
+    0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2];
+
+// Initial hash value
+const H: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+                     0x1f83d9ab, 0x5be0cd19];
+
+fn ch(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) ^ (!x & z)
+}
+
+fn ma(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) ^ (x & z) ^ (y & z)
+}
+
+fn sig0(x: u32) -> u32 {
+    x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
+}
+
+fn sig1(x: u32) -> u32 {
+    x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
+}
+
+fn sigma0(x: u32) -> u32 {
+    x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)
+}
+
+fn sigma1(x: u32) -> u32 {
+    x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
+}
+
+/// A block of a padded message, as big-endian words.
+pub type Block = [u32; 16];
+
+/// A SHA-256 digest, as big-endian words.
+pub type Digest = [u32; 8];
+
+fn compress(state: &mut Digest, block: &Block) {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(block);
+    for i in 16..64 {
+        w[i] = sigma1(w[i - 2])
+            .wrapping_add(w[i - 7])
+            .wrapping_add(sigma0(w[i - 15]))
+            .wrapping_add(w[i - 16]);
+    }
+
+    let (mut a, mut b, mut c, mut d) = (state[0], state[1], state[2], state[3]);
+    let (mut e, mut f, mut g, mut h) = (state[4], state[5], state[6], state[7]);
+    for round in 0..64 {
+        let temp1 = h.wrapping_add(sig1(e))
+            .wrapping_add(ch(e, f, g))
+            .wrapping_add(K[round])
+            .wrapping_add(w[round]);
+        let temp2 = sig0(a).wrapping_add(ma(a, b, c));
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (s, x) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *s = s.wrapping_add(*x);
+    }
+}
+
+/// Pads `message` as SHA-256 requires and stores it in `blocks`. Returns the
+/// number of blocks used, or None if there are too few.
+pub fn pad(message: &[u8], blocks: &mut [Block]) -> Option<usize> {
+    // The message, a 1 bit and the length in bits as 64 bit number
+    let len = (message.len() + 1 + 8 + 63) / 64;
+    if len > blocks.len() {
+        return None;
+    }
+    let bits = message.len() as u64 * 8;
+    for (i, block) in blocks[..len].iter_mut().enumerate() {
+        for (j, word) in block.iter_mut().enumerate() {
+            *word = 0;
+            for k in 0..4 {
+                let index = i * 64 + j * 4 + k;
+                let byte = if index < message.len() {
+                    message[index]
+                } else if index == message.len() {
+                    0x80
+                } else if index >= len * 64 - 8 {
+                    (bits >> (8 * (len * 64 - 1 - index))) as u8
+                } else {
+                    0
+                };
+                *word = (*word << 8) | byte as u32;
+            }
+        }
+    }
+    Some(len)
+}
+
+/// The SHA-256 digest of a padded message.
+pub fn sha256(blocks: &[Block]) -> Digest {
+    let mut state = H;
+    for block in blocks {
+        compress(&mut state, block);
+    }
+    state
+}
+
+/// The SHA-256 digest of the SHA-256 digest of a padded message.
+pub fn sha256d(blocks: &[Block]) -> Digest {
+    // The first digest, padded: 256 bits fit into one block
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&sha256(blocks));
+    block[8] = 0x80000000;
+    block[15] = 256;
+    sha256(&[block])
+}
+
+/// A message with its known digests.
+pub struct TestVector {
+    pub message: &'static [u8],
+    pub sha256: Digest,
+    pub sha256d: Digest,
+}
+
+/// The messages of the SHA-256 examples from NIST (FIPS 180-2 and the empty
+/// message), with their digests and those of the digests.
+pub const TEST_VECTORS: [TestVector; 4] = [
+    TestVector {
+        message: b"",
+        sha256: [0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c,
+                 0xa495991b, 0x7852b855],
+        sha256d: [0x5df6e0e2, 0x761359d3, 0x0a827505, 0x8e299fcc, 0x03815345, 0x45f55cf4,
+                  0x3e41983f, 0x5d4c9456],
+    },
+    TestVector {
+        message: b"abc",
+        sha256: [0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c,
+                 0xb410ff61, 0xf20015ad],
+        sha256d: [0x4f8b42c2, 0x2dd3729b, 0x519ba6f6, 0x8d2da7cc, 0x5b2d606d, 0x05daed5a,
+                  0xd5128cc0, 0x3e6c6358],
+    },
+    TestVector {
+        message: b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        sha256: [0x248d6a61, 0xd20638b8, 0xe5c02693, 0x0c3e6039, 0xa33ce459, 0x64ff2167,
+                 0xf6ecedd4, 0x19db06c1],
+        sha256d: [0x0cffe17f, 0x68954dac, 0x3a84fb14, 0x58bd5ec9, 0x92094497, 0x49b2b308,
+                  0xb7cb5581, 0x2f9563af],
+    },
+    TestVector {
+        message: b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+        sha256: [0xcf5b16a7, 0x78af8380, 0x036ce59e, 0x7b049237, 0x0b249b11, 0xe8f07a51,
+                 0xafac4503, 0x7afee9d1],
+        sha256d: [0xaccd7bd1, 0xcb0fcbd8, 0x5cf0ba5b, 0xa9694512, 0x7776373a, 0x7d47891e,
+                  0xb43ed6b1, 0xe2ee60fe],
+    },
+];
+
+// Enough for the longest test vector
+const MAX_BLOCKS: usize = 2;
+
+// How often each message is hashed, as in hash.cpp
+const ROUNDS: usize = 4;
+
+#[derive(Copy, Clone)]
+pub struct Message {
+    blocks: [Block; MAX_BLOCKS],
+    num_blocks: usize,
+    digest: Digest,
+}
+
+const EMPTY: Message = Message {
+    blocks: [[0; 16]; MAX_BLOCKS],
+    num_blocks: 0,
+    digest: [0; 8],
+};
+
+impl Equivalent for Message {
+    fn equivalent(&self, other: &Self, _: u32) -> bool {
+        self.digest == other.digest
+    }
+}
+
+pub struct Hash {
+    messages: &'static mut [Message],
+    rounds: usize,
+}
+
+impl Hash {
+    pub fn new() -> Self {
+        Hash { messages: &mut [], rounds: 0 }
+    }
+}
+
+impl Benchmark for Hash {
+    type Output = Message;
+
+    /// `size.n` is the number of messages, a multiple of 16 (the default),
+    /// which repeat TEST_VECTORS. `size.steps` is how often each one is
+    /// hashed (ROUNDS).
+    fn setup(&mut self, size: Size) -> bool {
+        let n = if size.n == 0 { 16 } else { size.n };
+        if n % 16 != 0 {
+            return false;
+        }
+        match pool::alloc(n, EMPTY) {
+            Some(messages) => self.messages = messages,
+            None => return false,
+        }
+        for (m, v) in self.messages.iter_mut().zip(TEST_VECTORS.iter().cycle()) {
+            m.num_blocks = pad(v.message, &mut m.blocks).unwrap();
+        }
+        self.rounds = if size.steps == 0 { ROUNDS } else { size.steps };
+        true
+    }
+
+    fn run_scalar(&mut self) {
+        for _ in 0..self.rounds {
+            nyuzi_support::run_scalar(self.messages,
+                                      |m| m.digest = sha256d(&m.blocks[..m.num_blocks]));
+        }
+    }
+
+    fn run_spmd(&mut self) {
+        for _ in 0..self.rounds {
+            nyuzi_support::run_vector(self.messages,
+                                      |m| m.digest = sha256d(&m.blocks[..m.num_blocks]));
+        }
+    }
+
+    fn verify(&self) -> bool {
+        self.messages
+            .iter()
+            .zip(TEST_VECTORS.iter().cycle())
+            .all(|(m, v)| m.digest == v.sha256d)
+    }
+
+    fn output(&self) -> &[Message] {
+        self.messages
+    }
+}
+
+bench! {
+    "hash_rust" => static HASH: Hash = Hash { messages: &mut [], rounds: 0 };
+}
@@ -0,0 +1,11 @@
+// Both variants of the benchmark, run on the host through the fallbacks of
+// the SPMD functions.
+#[macro_use]
+extern crate nyuzi_support;
+extern crate hash;
+
+use hash::Hash;
+
+host_tests! {
+    double_sha256 => Hash::new(), sizes: [32, 64];
+}
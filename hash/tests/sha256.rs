@@ -0,0 +1,28 @@
+// The hash functions of the benchmark against the test vectors, and padding
+// at the block boundaries.
+extern crate hash;
+
+use hash::{pad, sha256, sha256d, Block, TEST_VECTORS};
+
+#[test]
+fn test_vectors() {
+    for v in &TEST_VECTORS {
+        let mut blocks = [[0; 16]; 2];
+        let len = pad(v.message, &mut blocks).unwrap();
+        assert_eq!(sha256(&blocks[..len]), v.sha256);
+        assert_eq!(sha256d(&blocks[..len]), v.sha256d);
+    }
+}
+
+#[test]
+fn padding() {
+    let mut blocks: [Block; 3] = [[0; 16]; 3];
+    // The length needs the last 8 bytes of a block
+    assert_eq!(pad(&[0; 55], &mut blocks), Some(1));
+    assert_eq!(blocks[0][13], 0x00000080);
+    assert_eq!(blocks[0][15], 55 * 8);
+    assert_eq!(pad(&[0; 56], &mut blocks), Some(2));
+    assert_eq!(pad(&[0; 119], &mut blocks), Some(2));
+    assert_eq!(pad(&[0; 120], &mut blocks), Some(3));
+    assert_eq!(pad(&[0; 184], &mut blocks), None);
+}
@@ -0,0 +1,13 @@
+// Wall-clock times of both variants of the benchmark on the host, see
+// tests/host.rs.
+#![feature(test)]
+extern crate test;
+#[macro_use]
+extern crate nyuzi_support;
+extern crate mandelbrot;
+
+use mandelbrot::Mandelbrot;
+
+host_benches! {
+    image => Mandelbrot::new();
+}
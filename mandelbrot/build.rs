@@ -0,0 +1,75 @@
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+// The parameters of mandelbrot/mandelbrot.cpp
+const MAX_ITERATIONS: u32 = 255;
+const SCREEN_WIDTH: usize = 64;
+const SCREEN_HEIGHT: usize = 16;
+
+// Sizes (width, max iterations) with a reference besides the default one:
+// those in the SIZE_SWEEPS of bench.py and the sizes of the host tests
+const REFERENCE_SIZES: [(usize, u32); 4] = [(128, MAX_ITERATIONS),
+                                            (64, 1024),
+                                            (16, MAX_ITERATIONS),
+                                            (256, MAX_ITERATIONS)];
+
+/// The color of the point (x0, y0), computed the same way as by the
+/// benchmark, in f32. Those operations are exactly rounded, so the benchmark
+/// must reproduce the image exactly.
+fn escape(x0: f32, y0: f32, max_iterations: u32) -> u32 {
+    let mut x: f32 = 0.0;
+    let mut y: f32 = 0.0;
+    for iteration in 0..max_iterations {
+        let x_squared = x * x;
+        let y_squared = y * y;
+        if x_squared + y_squared >= 4.0 {
+            return ((iteration << 2) + 80) | 0xff000000;
+        }
+        y = x * y * 2.0 + y0;
+        x = x_squared - y_squared + x0;
+    }
+    0
+}
+
+/// The colors of an image `width` pixels wide, row by row.
+fn image(width: usize, max_iterations: u32) -> Vec<u32> {
+    let x_step = 2.5 / width as f32;
+    let y_step = 2.0 / SCREEN_HEIGHT as f32;
+    let mut image = Vec::new();
+    for row in 0..SCREEN_HEIGHT {
+        for col in 0..width {
+            image.push(escape(col as f32 * x_step - 2.0,
+                              row as f32 * y_step - 1.0,
+                              max_iterations));
+        }
+    }
+    image
+}
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let dest_path = Path::new(&out_dir).join("image.rs");
+    let mut f = File::create(&dest_path).unwrap();
+
+    write!(&mut f, "const MAX_ITERATIONS: u32 = {};", MAX_ITERATIONS).unwrap();
+    write!(&mut f, "const SCREEN_WIDTH: usize = {};", SCREEN_WIDTH).unwrap();
+    write!(&mut f, "const SCREEN_HEIGHT: usize = {};", SCREEN_HEIGHT).unwrap();
+
+    let mut sizes = vec![(SCREEN_WIDTH, MAX_ITERATIONS)];
+    sizes.extend_from_slice(&REFERENCE_SIZES);
+    write!(&mut f,
+           "static REFERENCES: [(usize, u32, &'static [u32]); {}] = [",
+           sizes.len())
+            .unwrap();
+    for (width, max_iterations) in sizes {
+        write!(&mut f,
+               "({}, {}, &{:?}),",
+               width,
+               max_iterations,
+               image(width, max_iterations))
+                .unwrap();
+    }
+    write!(&mut f, "];").unwrap();
+}
@@ -0,0 +1,135 @@
+// Port of mandelbrot/mandelbrot.cpp, which is adapted from an example app of
+// https://github.com/jbush001/NyuziProcessor/ (Copyright 2011-2015 Jeff Bush,
+// Apache License 2.0). Every pixel iterates until it escapes, so neighbouring
+// lanes of the SPMD variant diverge a lot.
+//
+// The benchmark is exported as `mandelbrot_rust`, to keep it apart from the
+// C++ one in the results.
+
+#![no_std]
+#![allow(dead_code)]
+#[macro_use]
+extern crate nyuzi_support;
+
+use nyuzi_support::benchmark::{Benchmark, Equivalent, Size};
+use nyuzi_support::pool;
+
+// MAX_ITERATIONS, SCREEN_WIDTH and SCREEN_HEIGHT (the same as in
+// mandelbrot.cpp), and REFERENCES, the colors of the image they describe and
+// of the sizes bench.py sweeps, as (width, max iterations, colors).
+include!(concat!(env!("OUT_DIR"), "/image.rs"));
+
+/// The color of the point (x0, y0): black if it does not escape within
+/// `max_iterations`, brighter the longer it takes otherwise.
+fn escape(x0: f32, y0: f32, max_iterations: u32) -> u32 {
+    let mut x = 0.0;
+    let mut y = 0.0;
+    for iteration in 0..max_iterations {
+        let x_squared = x * x;
+        let y_squared = y * y;
+        if x_squared + y_squared >= 4.0 {
+            // Increase contrast
+            return (iteration << 2).wrapping_add(80) | 0xff000000;
+        }
+        y = x * y * 2.0 + y0;
+        x = x_squared - y_squared + x0;
+    }
+    0
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Pixel {
+    x0: f32,
+    y0: f32,
+    color: u32,
+}
+
+const BLACK: Pixel = Pixel { x0: 0.0, y0: 0.0, color: 0 };
+
+impl Equivalent for Pixel {
+    fn equivalent(&self, other: &Self, _: u32) -> bool {
+        // The coordinates are inputs, the colors must match exactly
+        self.color == other.color
+    }
+}
+
+pub struct Mandelbrot {
+    pixels: &'static mut [Pixel],
+    max_iterations: u32,
+}
+
+impl Mandelbrot {
+    pub fn new() -> Self {
+        Mandelbrot { pixels: &mut [], max_iterations: 0 }
+    }
+}
+
+impl Benchmark for Mandelbrot {
+    type Output = Pixel;
+
+    /// `size.n` is the width of the image, a multiple of 16 (the default is
+    /// SCREEN_WIDTH), and `size.steps` the number of iterations after which a
+    /// point counts as not escaping (MAX_ITERATIONS). The image always covers
+    /// the same part of the plane, in SCREEN_HEIGHT rows.
+    fn setup(&mut self, size: Size) -> bool {
+        let width = if size.n == 0 { SCREEN_WIDTH } else { size.n };
+        if width % 16 != 0 {
+            return false;
+        }
+        match pool::alloc(width * SCREEN_HEIGHT, BLACK) {
+            Some(pixels) => self.pixels = pixels,
+            None => return false,
+        }
+        self.max_iterations = if size.steps == 0 {
+            MAX_ITERATIONS
+        } else {
+            size.steps as u32
+        };
+
+        let x_step = 2.5 / width as f32;
+        let y_step = 2.0 / SCREEN_HEIGHT as f32;
+        for (i, pixel) in self.pixels.iter_mut().enumerate() {
+            let (row, col) = (i / width, i % width);
+            pixel.x0 = col as f32 * x_step - 2.0;
+            pixel.y0 = row as f32 * y_step - 1.0;
+        }
+        true
+    }
+
+    fn run_scalar(&mut self) {
+        let max_iterations = self.max_iterations;
+        nyuzi_support::run_scalar(self.pixels,
+                                  |p| p.color = escape(p.x0, p.y0, max_iterations));
+    }
+
+    fn run_spmd(&mut self) {
+        let max_iterations = self.max_iterations;
+        nyuzi_support::run_vector(self.pixels,
+                                  |p| p.color = escape(p.x0, p.y0, max_iterations));
+    }
+
+    /// Compares with the image of build.rs for the size. Other sizes have
+    /// none and are compared with the colors computed by `escape`, which only
+    /// checks the SPMD variant.
+    fn verify(&self) -> bool {
+        let width = self.pixels.len() / SCREEN_HEIGHT;
+        for &(w, max_iterations, reference) in REFERENCES.iter() {
+            if w == width && max_iterations == self.max_iterations {
+                return self.pixels.iter().zip(reference.iter()).all(|(p, &c)| p.color == c);
+            }
+        }
+        let max_iterations = self.max_iterations;
+        self.pixels.iter().all(|p| p.color == escape(p.x0, p.y0, max_iterations))
+    }
+
+    fn output(&self) -> &[Pixel] {
+        self.pixels
+    }
+}
+
+bench! {
+    "mandelbrot_rust" => static MANDELBROT: Mandelbrot = Mandelbrot {
+        pixels: &mut [],
+        max_iterations: 0,
+    };
+}
@@ -0,0 +1,11 @@
+// Both variants of the benchmark, run on the host through the fallbacks of
+// the SPMD functions.
+#[macro_use]
+extern crate nyuzi_support;
+extern crate mandelbrot;
+
+use mandelbrot::Mandelbrot;
+
+host_tests! {
+    image => Mandelbrot::new(), sizes: [16, 256];
+}
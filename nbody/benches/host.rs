@@ -0,0 +1,19 @@
+// Wall-clock times of all variants of every benchmark on the host, see
+// tests/host.rs.
+#![feature(test)]
+extern crate test;
+#[macro_use]
+extern crate nyuzi_support;
+extern crate nbody;
+
+use nbody::NBody;
+
+#[cfg(not(feature = "f64"))]
+host_benches! {
+    simulation => NBody::new(), intrin;
+}
+
+#[cfg(feature = "f64")]
+host_benches! {
+    simulation => NBody::new();
+}
@@ -1,21 +1,32 @@
 extern crate cgmath;
+extern crate nyuzi_support;
 extern crate rand;
-use cgmath::{Point3, Vector3};
+use cgmath::{InnerSpace, Point3, Vector3, Zero};
 use rand::{Rand, Rng, SeedableRng, XorShiftRng};
 use std::env;
+use std::fmt::Debug;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::Path;
 
+const BENCH_BODIES: usize = 32;
+
+const BENCH_TICKS: usize = 10;
+
+// Sizes (bodies, ticks) with a reference besides the default one: those in
+// the SIZE_SWEEPS of bench.py and the sizes of the host tests
+const REFERENCE_SIZES: [(usize, usize); 3] = [(16, BENCH_TICKS), (64, BENCH_TICKS), (64, 4)];
+
 const INITIAL_VELOCITY: f32 = 8.0; // set to 0.0 to turn off.
 
-pub struct Body {
-    pub position: Point3<f32>,
-    pub velocity: Vector3<f32>,
-    pub velocity2: Vector3<f32>,
+#[derive(Copy, Clone)]
+pub struct Body<S> {
+    pub position: Point3<S>,
+    pub velocity: Vector3<S>,
+    pub velocity2: Vector3<S>,
 }
 
-fn create_data<R: Rng>(num_bodies: usize, rng: &mut R) -> Vec<Body> {
+fn create_data<R: Rng>(num_bodies: usize, rng: &mut R) -> Vec<Body<f32>> {
     (0..num_bodies)
         .map(|_| {
             let position = Point3 {
@@ -45,7 +56,176 @@ fn create_data<R: Rng>(num_bodies: usize, rng: &mut R) -> Vec<Body> {
         .collect()
 }
 
-fn write_bodies<W: Write>(w: &mut W, bodies: &[Body]) -> Result<(), io::Error> {
+// The reference simulation, the same as the benchmark's but on cgmath, in the
+// precision of the build: the bodies diverge too quickly for an f64 reference
+// to check an f32 run (they end up some 18% apart). Both do the arithmetic in
+// the same order as the benchmark, and start from the inputs as written, so
+// that the f64 build of the benchmark starts from exactly the same values.
+//
+// For the same reason the simulation calls the math functions of
+// nyuzi_support rather than std's: cargo builds the build dependency with the
+// same features as the benchmark's dependency, so the reference uses the math
+// backend of the build (with the fast tier, a libm reference ends up some 36%
+// of the distance travelled away).
+
+fn widen(x: f32) -> f64 {
+    format!("{:?}", x).parse().unwrap()
+}
+
+fn same(x: f32) -> f32 {
+    x
+}
+
+macro_rules! simulation {
+    ($simulate: ident, $float: ident, $convert: ident, $($math: ident)::+) => {
+        fn $simulate(bodies: &[Body<f32>], ticks: usize) -> Vec<Body<$float>> {
+            use std::$float::consts::PI;
+            use nyuzi_support::$($math)::+::{sin, cos, sqrt};
+
+            fn magnitude(v: Vector3<$float>) -> $float {
+                sqrt(v.magnitude2())
+            }
+
+            fn convert_vector(v: Vector3<f32>) -> Vector3<$float> {
+                Vector3::new($convert(v.x), $convert(v.y), $convert(v.z))
+            }
+
+            fn convert_body(body: &Body<f32>) -> Body<$float> {
+                Body {
+                    position: Point3::new($convert(body.position.x),
+                                          $convert(body.position.y),
+                                          $convert(body.position.z)),
+                    velocity: convert_vector(body.velocity),
+                    velocity2: convert_vector(body.velocity2),
+                }
+            }
+
+            fn next_velocity(time: usize,
+                             prev: &Body<$float>,
+                             bodies: &[Body<$float>])
+                             -> (Vector3<$float>, Vector3<$float>) {
+                let time = time as $float;
+                let center = Point3 {
+                    x: cos(time / 22.0) * -4200.0,
+                    y: sin(time / 14.0) * 9200.0,
+                    z: sin(time / 27.0) * 6000.0,
+                };
+
+                let max_distance = 3400.0;
+                let pull_strength = 0.042;
+
+                let zone = 400.0;
+                let repel = 100.0;
+                let align = 300.0;
+                let attract = 100.0;
+
+                let (speed_limit, attract_power) = if time < 500.0 {
+                    (2000.0, 100.9)
+                } else {
+                    (0.2, 20.9)
+                };
+
+                let zone_sqrd = 3.0 * (zone * zone);
+
+                let mut acc = Vector3::zero();
+                let mut acc2 = Vector3::zero();
+
+                let dir_to_center = center - prev.position;
+                let dist_to_center = magnitude(dir_to_center);
+
+                if dist_to_center > max_distance {
+                    let velc = if time < 200.0 {
+                        0.2
+                    } else {
+                        (dist_to_center - max_distance) * pull_strength
+                    };
+                    acc += (dir_to_center / dist_to_center) * velc;
+                }
+
+                let mut diff = Vector3::zero();
+                let mut diff2 = Vector3::zero();
+                for body in bodies {
+                    let r = body.position - prev.position;
+                    let dist_sqrd = r.magnitude2();
+                    if dist_sqrd >= zone_sqrd || r == Vector3::zero() {
+                        continue;
+                    }
+
+                    let length = sqrt(dist_sqrd);
+                    let percent = dist_sqrd / zone_sqrd;
+
+                    if dist_sqrd < repel {
+                        let f = (repel / percent - 1.0) * 0.025;
+                        let normal = (r / length) * f;
+                        diff += normal;
+                        diff2 += normal;
+                    } else if dist_sqrd < align {
+                        let adjusted_percent = (percent - repel) / (align - repel);
+                        let q = (0.5 - cos(adjusted_percent * PI * 2.0) * 0.5 + 0.5) * 100.9;
+                        diff += (body.velocity2 / magnitude(body.velocity2)) * q;
+                        diff2 += (prev.velocity / magnitude(prev.velocity)) * q;
+                    }
+
+                    if dist_sqrd > attract {
+                        let adjusted_percent2 = (percent - attract) / (1.0 - attract);
+                        let c = (1.0 - (cos(adjusted_percent2 * PI * 2.0) * 0.5 + 0.5)) *
+                                attract_power;
+                        let d = (r / length) * c;
+                        diff += d;
+                        diff2 -= d;
+                    }
+                }
+
+                acc += diff;
+                acc2 += diff2;
+
+                if time > 500.0 {
+                    if acc.magnitude2() > speed_limit {
+                        acc *= 0.015;
+                    }
+                    if acc2.magnitude2() > speed_limit {
+                        acc2 *= 0.015;
+                    }
+                }
+
+                let mut new = prev.velocity + acc;
+                let mut new2 = prev.velocity2 + acc2;
+
+                if time < 500.0 {
+                    if new2.magnitude2() > speed_limit {
+                        new2 *= 0.15;
+                    }
+                    if new.magnitude2() > speed_limit {
+                        new *= 0.15;
+                    }
+                }
+
+                (new, new2)
+            }
+
+            let mut bodies: Vec<Body<$float>> = bodies.iter().map(convert_body).collect();
+            for time in 0..ticks {
+                bodies = bodies
+                    .iter()
+                    .map(|prev| {
+                        let (velocity, velocity2) = next_velocity(time, prev, &bodies);
+                        Body {
+                            position: prev.position + (velocity - velocity2),
+                            velocity: velocity,
+                            velocity2: velocity2,
+                        }
+                    })
+                    .collect();
+            }
+            bodies
+        }
+    }
+}
+
+simulation!(simulate_f32, f32, same, math);
+simulation!(simulate_f64, f64, widen, math::f64);
+
+fn write_bodies<W: Write, S: Debug + Copy>(w: &mut W, bodies: &[Body<S>]) -> Result<(), io::Error> {
     writeln!(w, "[")?;
     for &Body {
              position: p,
@@ -54,23 +234,23 @@ fn write_bodies<W: Write>(w: &mut W, bodies: &[Body]) -> Result<(), io::Error> {
          } in bodies {
         writeln!(w, "    Body {{")?;
         writeln!(w,
-                 "         position: Vector3 {{ x: {:?}f32, y: {:?}f32, z: {:?}f32 }},",
+                 "         position: Point3 {{ x: {:?}, y: {:?}, z: {:?} }},",
                  p.x,
                  p.y,
                  p.z)?;
         writeln!(w,
-                 "         velocity: Vector3 {{ x: {:?}f32, y: {:?}f32, z: {:?}f32 }},",
+                 "         velocity: Vector3 {{ x: {:?}, y: {:?}, z: {:?} }},",
                  v.x,
                  v.y,
                  v.z)?;
         writeln!(w,
-                 "         velocity2: Vector3 {{ x: {:?}f32, y: {:?}f32, z: {:?}f32 }},",
+                 "         velocity2: Vector3 {{ x: {:?}, y: {:?}, z: {:?} }},",
                  v2.x,
                  v2.y,
                  v2.z)?;
         writeln!(w, "    }},")?;
     }
-    writeln!(w, "];")?;
+    write!(w, "]")?;
     Ok(())
 }
 
@@ -81,7 +261,29 @@ fn main() {
     let mut f = File::create(&dest_path).unwrap();
 
     let mut rng = XorShiftRng::from_seed([0, 1, 2, 3]);
-    let bodies = create_data(32, &mut rng);
+    let bodies = create_data(BENCH_BODIES, &mut rng);
+    writeln!(&mut f, "const BENCH_BODIES: usize = {};", BENCH_BODIES).unwrap();
+    writeln!(&mut f, "const BENCH_TICKS: usize = {};", BENCH_TICKS).unwrap();
     write!(&mut f, "static BODIES_INIT: [Body; BENCH_BODIES] = ").unwrap();
     write_bodies(&mut f, &bodies).unwrap();
+    writeln!(&mut f, ";").unwrap();
+
+    // The inputs of a simulation of n bodies repeat those of the default one
+    let mut sizes = vec![(BENCH_BODIES, BENCH_TICKS)];
+    sizes.extend_from_slice(&REFERENCE_SIZES);
+    writeln!(&mut f,
+             "static REFERENCES: [(usize, usize, &'static [Body]); {}] = [",
+             sizes.len())
+            .unwrap();
+    for (n, ticks) in sizes {
+        let init: Vec<Body<f32>> = bodies.iter().cycle().take(n).cloned().collect();
+        write!(&mut f, "({}, {}, &", n, ticks).unwrap();
+        if env::var_os("CARGO_FEATURE_F64").is_some() {
+            write_bodies(&mut f, &simulate_f64(&init, ticks)).unwrap();
+        } else {
+            write_bodies(&mut f, &simulate_f32(&init, ticks)).unwrap();
+        }
+        writeln!(&mut f, "),").unwrap();
+    }
+    writeln!(&mut f, "];").unwrap();
 }
@@ -0,0 +1,295 @@
+// The hand-vectorised (`intrin`) variant: each vector holds one coordinate of
+// 16 consecutive bodies, which are then compared against every body in turn.
+// The branches of `next_velocity` become masks, and every lane performs the
+// same operations in the same order as the scalar code, so on the host both
+// give exactly the same bodies.
+//
+// There are no vectors of f64, so this is compiled in f32 builds only.
+use core::slice;
+use nyuzi_support::intrin::{F32x16, LANES, Mask, U32x16};
+use float::{sin, cos, sqrt, PI};
+use super::{Body, NBodyBenchmark};
+
+// A `Body` as seen by `gather` and `scatter`: 9 floats, position, velocity and
+// velocity2
+const BODY_FLOATS: usize = 9;
+const POSITION: u32 = 0;
+const VELOCITY: u32 = 3;
+const VELOCITY2: u32 = 6;
+
+fn floats(bodies: &[Body]) -> &[f32] {
+    unsafe { slice::from_raw_parts(bodies.as_ptr() as *const f32, bodies.len() * BODY_FLOATS) }
+}
+
+fn floats_mut(bodies: &mut [Body]) -> &mut [f32] {
+    unsafe {
+        slice::from_raw_parts_mut(bodies.as_mut_ptr() as *mut f32, bodies.len() * BODY_FLOATS)
+    }
+}
+
+/// A `Vector3` (or `Point3`) per lane.
+#[derive(Copy, Clone)]
+struct Vector3x16 {
+    x: F32x16,
+    y: F32x16,
+    z: F32x16,
+}
+
+impl Vector3x16 {
+    fn splat(x: f32, y: f32, z: f32) -> Self {
+        Vector3x16 {
+            x: F32x16::splat(x),
+            y: F32x16::splat(y),
+            z: F32x16::splat(z),
+        }
+    }
+
+    fn zero() -> Self {
+        Vector3x16::splat(0.0, 0.0, 0.0)
+    }
+
+    /// The vectors at `offset` in the bodies at `indices`.
+    fn gather(xs: &[f32], indices: U32x16, offset: u32) -> Self {
+        let indices = indices * U32x16::splat(BODY_FLOATS as u32) + U32x16::splat(offset);
+        Vector3x16 {
+            x: F32x16::gather(xs, indices),
+            y: F32x16::gather(xs, indices + U32x16::splat(1)),
+            z: F32x16::gather(xs, indices + U32x16::splat(2)),
+        }
+    }
+
+    fn scatter(self, xs: &mut [f32], indices: U32x16, offset: u32) {
+        let indices = indices * U32x16::splat(BODY_FLOATS as u32) + U32x16::splat(offset);
+        self.x.scatter(xs, indices);
+        self.y.scatter(xs, indices + U32x16::splat(1));
+        self.z.scatter(xs, indices + U32x16::splat(2));
+    }
+
+    fn add(self, other: Self) -> Self {
+        Vector3x16 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Vector3x16 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+
+    fn mul(self, s: F32x16) -> Self {
+        Vector3x16 {
+            x: self.x * s,
+            y: self.y * s,
+            z: self.z * s,
+        }
+    }
+
+    fn div(self, s: F32x16) -> Self {
+        Vector3x16 {
+            x: self.x / s,
+            y: self.y / s,
+            z: self.z / s,
+        }
+    }
+
+    fn is_zero(self) -> Mask {
+        let zero = F32x16::splat(0.0);
+        self.x.eq(zero) & self.y.eq(zero) & self.z.eq(zero)
+    }
+
+    fn magnitude2(self) -> F32x16 {
+        F32x16::splat(0.0) + self.x * self.x + self.y * self.y + self.z * self.z
+    }
+
+    fn magnitude(self) -> F32x16 {
+        self.magnitude2().map(sqrt)
+    }
+
+    /// The lanes of `self` where `mask` is set, those of `other` elsewhere.
+    fn select(self, mask: Mask, other: Self) -> Self {
+        Vector3x16 {
+            x: mask.select(self.x, other.x),
+            y: mask.select(self.y, other.y),
+            z: mask.select(self.z, other.z),
+        }
+    }
+}
+
+impl<'a> NBodyBenchmark<'a> {
+    pub fn tick_intrin(&mut self) {
+        let (in_bodies, out_bodies) = if (self.time & 1) == 0 {
+            (&*self.bodies.0, &mut *self.bodies.1)
+        } else {
+            (&*self.bodies.1, &mut *self.bodies.0)
+        };
+
+        let time = self.time;
+        let (prev_floats, out_floats) = (floats(in_bodies), floats_mut(out_bodies));
+        for chunk in 0..in_bodies.len() / LANES {
+            let indices = U32x16::splat((chunk * LANES) as u32) + U32x16::lane_ids();
+            let position = Vector3x16::gather(prev_floats, indices, POSITION);
+            let velocity = Vector3x16::gather(prev_floats, indices, VELOCITY);
+            let velocity2 = Vector3x16::gather(prev_floats, indices, VELOCITY2);
+
+            let (vel, vel2) = next_velocity(time, position, velocity, velocity2, in_bodies);
+            vel.scatter(out_floats, indices, VELOCITY);
+            vel2.scatter(out_floats, indices, VELOCITY2);
+
+            let next_velocity = vel.sub(vel2);
+            position.add(next_velocity).scatter(out_floats, indices, POSITION);
+        }
+
+        self.time += 1;
+    }
+}
+
+/// `super::next_velocity` of 16 bodies.
+fn next_velocity(time: usize,
+                 position: Vector3x16,
+                 velocity: Vector3x16,
+                 velocity2: Vector3x16,
+                 bodies: &[Body])
+                 -> (Vector3x16, Vector3x16) {
+    let time = time as f32;
+    let center = Vector3x16::splat(cos(time / 22.0) * -4200.0,
+                                   sin(time / 14.0) * 9200.0,
+                                   sin(time / 27.0) * 6000.0);
+
+    // pull to center
+    let max_distance = 3400.0;
+    let pull_strength = 0.042;
+
+    // zones
+    let zone = 400.0;
+    let repel = 100.0;
+    let align = 300.0;
+    let attract = 100.0;
+
+    let (speed_limit, attract_power);
+    if time < 500.0 {
+        speed_limit = 2000.0;
+        attract_power = 100.9;
+    } else {
+        speed_limit = 0.2;
+        attract_power = 20.9;
+    }
+
+    let zone_sqrd = F32x16::splat(3.0 * (zone * zone));
+
+    let mut acc = Vector3x16::zero();
+    let mut acc2 = Vector3x16::zero();
+
+    let dir_to_center = center.sub(position);
+    let dist_to_center = dir_to_center.magnitude();
+
+    // orient to center
+    let far = dist_to_center.gt(F32x16::splat(max_distance));
+    if far.any() {
+        let velc = if time < 200.0 {
+            F32x16::splat(0.2)
+        } else {
+            (dist_to_center - F32x16::splat(max_distance)) * F32x16::splat(pull_strength)
+        };
+
+        let diff = dir_to_center.div(dist_to_center).mul(velc);
+        acc = acc.add(diff).select(far, acc);
+    }
+
+    let mut diff = Vector3x16::zero();
+    let mut diff2 = Vector3x16::zero();
+    for body in bodies {
+        let r = Vector3x16::splat(body.position.x, body.position.y, body.position.z)
+            .sub(position);
+
+        // make sure we are not testing the particle against its own position
+        let are_same = r.is_zero();
+
+        let dist_sqrd = r.magnitude2();
+
+        let near = dist_sqrd.lt(zone_sqrd) & !are_same;
+        if near.none() {
+            continue;
+        }
+        let length = dist_sqrd.map(sqrt);
+        let percent = dist_sqrd / zone_sqrd;
+
+        let repelled = near & dist_sqrd.lt(F32x16::splat(repel));
+        if repelled.any() {
+            let f = (F32x16::splat(repel) / percent - F32x16::splat(1.0)) * F32x16::splat(0.025);
+            let normal = r.div(length).mul(f);
+            diff = diff.add(normal).select(repelled, diff);
+            diff2 = diff2.add(normal).select(repelled, diff2);
+        }
+
+        let aligned = near & !dist_sqrd.lt(F32x16::splat(repel)) &
+                      dist_sqrd.lt(F32x16::splat(align));
+        if aligned.any() {
+            let thresh_delta = align - repel;
+            let adjusted_percent = (percent - F32x16::splat(repel)) / F32x16::splat(thresh_delta);
+            let q = (F32x16::splat(0.5) -
+                     (adjusted_percent * F32x16::splat(PI) * F32x16::splat(2.0)).map(cos) *
+                     F32x16::splat(0.5) + F32x16::splat(0.5)) * F32x16::splat(100.9);
+
+            // normalize vel2 and multiply by factor
+            let vel2_length = body.velocity2.magnitude();
+            let vel2 = body.velocity2 / vel2_length;
+            let vel2 = Vector3x16::splat(vel2.x, vel2.y, vel2.z).mul(q);
+
+            // normalize own velocity
+            let vel_length = velocity.magnitude();
+            let vel = velocity.div(vel_length).mul(q);
+
+            diff = diff.add(vel2).select(aligned, diff);
+            diff2 = diff2.add(vel).select(aligned, diff2);
+        }
+
+        let attracted = near & dist_sqrd.gt(F32x16::splat(attract));
+        if attracted.any() {
+            // attract
+            let thresh_delta2 = 1.0 - attract;
+            let adjusted_percent2 = (percent - F32x16::splat(attract)) /
+                                    F32x16::splat(thresh_delta2);
+            let c = (F32x16::splat(1.0) -
+                     ((adjusted_percent2 * F32x16::splat(PI) * F32x16::splat(2.0)).map(cos) *
+                      F32x16::splat(0.5) + F32x16::splat(0.5))) *
+                    F32x16::splat(attract_power);
+
+            // normalize the distance vector
+            let d = r.div(length).mul(c);
+
+            diff = diff.add(d).select(attracted, diff);
+            diff2 = diff2.sub(d).select(attracted, diff2);
+        }
+    }
+
+    acc = acc.add(diff);
+    acc2 = acc2.add(diff2);
+
+    // Speed limits
+    let speed_limit = F32x16::splat(speed_limit);
+    if time > 500.0 {
+        let acc_squared = acc.magnitude2();
+        acc = acc.mul(F32x16::splat(0.015)).select(acc_squared.gt(speed_limit), acc);
+
+        let acc_squared2 = acc2.magnitude2();
+        acc2 = acc2.mul(F32x16::splat(0.015)).select(acc_squared2.gt(speed_limit), acc2);
+    }
+
+    let mut new = velocity.add(acc);
+    let mut new2 = velocity2.add(acc2);
+
+    if time < 500.0 {
+        let acs = new2.magnitude2();
+        new2 = new2.mul(F32x16::splat(0.15)).select(acs.gt(speed_limit), new2);
+
+        let acs2 = new.magnitude2();
+        new = new.mul(F32x16::splat(0.15)).select(acs2.gt(speed_limit), new);
+    }
+
+    (new, new2)
+}
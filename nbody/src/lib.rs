@@ -34,19 +34,49 @@
 // [1]: https://github.com/IntelLabs/RiverTrail/blob/master/examples/nbody-webgl/NBody.js
 #![no_std]
 #![allow(dead_code)]
+#[macro_use]
 extern crate nyuzi_support;
 
-use nyuzi_support::{black_box, spmd_zip2, sin, cos, sqrt};
-use core::f32::consts::PI;
+use nyuzi_support::{black_box, spmd_zip2};
+use nyuzi_support::linalg;
+use nyuzi_support::timer::Region;
+#[cfg(not(feature = "f64"))]
+use nyuzi_support::benchmark::IntrinBenchmark;
+use nyuzi_support::benchmark::{Benchmark, Equivalent, Size};
+use nyuzi_support::pool;
+use float::{Float, sin, cos, sqrt, PI};
+
+#[cfg(not(feature = "f64"))]
+mod intrin;
+
+type Vector3 = linalg::Vector3<Float>;
+type Point3 = linalg::Point3<Float>;
+
+// The `f64` feature switches the whole simulation to double precision
+#[cfg(not(feature = "f64"))]
+mod float {
+    pub type Float = f32;
+    pub use nyuzi_support::{sin, cos, sqrt};
+    pub use core::f32::consts::PI;
+}
+
+#[cfg(feature = "f64")]
+mod float {
+    pub type Float = f64;
+    pub use nyuzi_support::math::f64::{sin, cos, sqrt};
+    pub use core::f64::consts::PI;
+}
 
 pub struct NBodyBenchmark<'a> {
     time: usize,
     bodies: (&'a mut [Body], &'a mut [Body]),
 }
 
+// repr(C) so that the intrin variant can gather the fields
 #[derive(Copy, Clone)]
+#[repr(C)]
 pub struct Body {
-    pub position: Vector3,
+    pub position: Point3,
     pub velocity: Vector3,
     pub velocity2: Vector3,
 }
@@ -94,8 +124,8 @@ impl<'a> NBodyBenchmark<'a> {
 }
 
 fn next_velocity(time: usize, prev: &Body, bodies: &[Body]) -> (Vector3, Vector3) {
-    let time = time as f32;
-    let center = Vector3 {
+    let time = time as Float;
+    let center = Point3 {
         x: cos(time / 22.0) * -4200.0,
         y: sin(time / 14.0) * 9200.0,
         z: sin(time / 27.0) * 6000.0,
@@ -227,156 +257,213 @@ fn next_velocity(time: usize, prev: &Body, bodies: &[Body]) -> (Vector3, Vector3
     (new, new2)
 }
 
-// Custom implementation (no code shared) of cgmath interfaces,
-// to make the above code work with less modification
-use core::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div};
+// Bench data: BENCH_BODIES, BENCH_TICKS, BODIES_INIT and REFERENCES, the
+// bodies after a number of ticks as simulated by build.rs, for the default
+// size and the ones bench.py sweeps, as (bodies, ticks, bodies after them)
 
-#[derive(Copy, Clone, PartialEq)]
-pub struct Vector3 {
-    x: f32,
-    y: f32,
-    z: f32,
-}
+include!(concat!(env!("OUT_DIR"), "/bodies.rs"));
 
-impl Vector3 {
-    fn zero() -> Self {
-        Vector3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        }
-    }
+// Bench interface
 
-    fn magnitude2(&self) -> f32 {
-        self.x * self.x + self.y * self.y + self.z * self.z
-    }
+const DUMMY_BODY: Body = Body {
+    position: Point3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    },
+    velocity: Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    },
+    velocity2: Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    },
+};
 
-    fn magnitude(&self) -> f32 {
-        sqrt(self.magnitude2())
-    }
+pub struct NBody {
+    bodies1: &'static mut [Body],
+    bodies2: &'static mut [Body],
+    ticks: usize,
 }
 
-impl Add for Vector3 {
-    type Output = Vector3;
-
-    fn add(mut self, rhs: Vector3) -> Vector3 {
-        self += rhs;
-        self
+impl NBody {
+    pub fn new() -> Self {
+        NBody {
+            bodies1: &mut [],
+            bodies2: &mut [],
+            ticks: 0,
+        }
     }
-}
 
-impl AddAssign for Vector3 {
-    fn add_assign(&mut self, rhs: Vector3) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-        self.z += rhs.z;
+    fn benchmark(&mut self) -> NBodyBenchmark {
+        NBodyBenchmark {
+            time: 0,
+            bodies: (&mut *self.bodies1, &mut *self.bodies2),
+        }
     }
 }
 
-impl Sub for Vector3 {
-    type Output = Vector3;
-
-    fn sub(mut self, rhs: Vector3) -> Vector3 {
-        self -= rhs;
-        self
-    }
+/// Bodies for a simulation of `n` bodies, which repeat BODIES_INIT.
+fn alloc_bodies(n: usize) -> Option<&'static mut [Body]> {
+    pool::alloc(n, DUMMY_BODY).map(|bodies| {
+        for (body, init) in bodies.iter_mut().zip(BODIES_INIT.iter().cycle()) {
+            *body = *init;
+        }
+        bodies
+    })
 }
 
-impl SubAssign for Vector3 {
-    fn sub_assign(&mut self, rhs: Vector3) {
-        self.x -= rhs.x;
-        self.y -= rhs.y;
-        self.z -= rhs.z;
-    }
+// Tolerance of `verify`, relative to how far a body moved in the reference.
+// build.rs simulates in the same precision and order and with the same math
+// backend, and on the host the results match exactly. All bodies start at the
+// same point, though, and the repulsion between nearly coincident bodies
+// amplifies rounding differences, so this leaves room for a libm on the
+// device that rounds differently from the host's (libm and the accurate tier
+// of the Rust port end up 1.5e-3 apart).
+#[cfg(not(feature = "f64"))]
+fn tolerance() -> Float {
+    2e-3
 }
 
-impl Mul<f32> for Vector3 {
-    type Output = Vector3;
+#[cfg(feature = "f64")]
+fn tolerance() -> Float {
+    1e-6
+}
 
-    fn mul(mut self, rhs: f32) -> Vector3 {
-        self *= rhs;
-        self
+/// Whether all bodies ended up within the tolerance of `expected`.
+fn close(actual: &[Body], expected: &[Body]) -> bool {
+    let tolerance = tolerance();
+    let bodies = actual.iter().zip(expected.iter()).zip(BODIES_INIT.iter().cycle());
+    for ((body, expected), init) in bodies {
+        let error = (body.position - expected.position).magnitude();
+        let distance = (expected.position - init.position).magnitude();
+        // Also false for NaNs
+        if !(error <= tolerance * distance) {
+            return false;
+        }
     }
+    true
 }
-impl MulAssign<f32> for Vector3 {
-    fn mul_assign(&mut self, rhs: f32) {
-        self.x *= rhs;
-        self.y *= rhs;
-        self.z *= rhs;
+
+impl Equivalent for Body {
+    fn equivalent(&self, other: &Self, max_ulps: u32) -> bool {
+        self.position.equivalent(&other.position, max_ulps) &&
+        self.velocity.equivalent(&other.velocity, max_ulps) &&
+        self.velocity2.equivalent(&other.velocity2, max_ulps)
     }
 }
 
-impl Div<f32> for Vector3 {
-    type Output = Vector3;
+impl Benchmark for NBody {
+    type Output = Body;
 
-    fn div(self, rhs: f32) -> Vector3 {
-        Vector3 {
-            x: self.x / rhs,
-            y: self.y / rhs,
-            z: self.z / rhs,
+    /// `size.n` is the number of bodies, a multiple of 16 (the default is
+    /// BENCH_BODIES), and `size.steps` the number of ticks (BENCH_TICKS).
+    fn setup(&mut self, size: Size) -> bool {
+        let n = if size.n == 0 { BENCH_BODIES } else { size.n };
+        if n % 16 != 0 {
+            return false;
         }
+        match (alloc_bodies(n), alloc_bodies(n)) {
+            (Some(bodies1), Some(bodies2)) => {
+                self.bodies1 = bodies1;
+                self.bodies2 = bodies2;
+            }
+            _ => return false,
+        }
+        self.ticks = if size.steps == 0 { BENCH_TICKS } else { size.steps };
+        true
     }
-}
 
-// Bench data
-
-const BENCH_BODIES: usize = 32;
+    fn run_scalar(&mut self) {
+        let ticks = self.ticks;
+        let mut nbody = black_box(self.benchmark());
+        let _ticks = Region::new("ticks");
+        for _ in 0..ticks {
+            nbody.tick_seq();
+        }
+        black_box(&mut nbody);
+    }
 
-const BENCH_TICKS: usize = 10;
+    fn run_spmd(&mut self) {
+        let ticks = self.ticks;
+        let mut nbody = black_box(self.benchmark());
+        let _ticks = Region::new("ticks");
+        for _ in 0..ticks {
+            nbody.tick_par();
+        }
+        black_box(&mut nbody);
+    }
 
-include!(concat!(env!("OUT_DIR"), "/bodies.rs"));
+    /// Compares with the reference of build.rs for the size. Other sizes
+    /// have none and are compared with a scalar run, which only checks the
+    /// SPMD and intrin variants.
+    fn verify(&self) -> bool {
+        let n = self.bodies1.len();
+        for &(bodies, ticks, reference) in REFERENCES.iter() {
+            if bodies == n && ticks == self.ticks {
+                return close(self.output(), reference);
+            }
+        }
 
-// Bench interface
+        let mut expected = match (alloc_bodies(n), alloc_bodies(n)) {
+            (Some(bodies1), Some(bodies2)) => {
+                NBody {
+                    bodies1: bodies1,
+                    bodies2: bodies2,
+                    ticks: self.ticks,
+                }
+            }
+            _ => return false,
+        };
+        {
+            let mut nbody = expected.benchmark();
+            for _ in 0..self.ticks {
+                nbody.tick_seq();
+            }
+        }
+        close(self.output(), expected.output())
+    }
 
-const DUMMY_BODY: Body = Body {
-    position: Vector3 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
-    },
-    velocity: Vector3 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
-    },
-    velocity2: Vector3 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
-    },
-};
-static mut BODIES1: [Body; BENCH_BODIES] = [DUMMY_BODY; BENCH_BODIES];
-static mut BODIES2: [Body; BENCH_BODIES] = [DUMMY_BODY; BENCH_BODIES];
-
-unsafe fn get_data() -> NBodyBenchmark<'static> {
-    BODIES1.copy_from_slice(&BODIES_INIT);
-    BODIES2.copy_from_slice(&BODIES_INIT);
-    black_box(NBodyBenchmark {
-                  time: 0,
-                  bodies: (&mut BODIES1, &mut BODIES2),
-              })
+    /// The bodies after the last tick.
+    fn output(&self) -> &[Body] {
+        if self.ticks % 2 == 0 {
+            self.bodies1
+        } else {
+            self.bodies2
+        }
+    }
 }
 
-#[no_mangle]
-#[cfg(variant="scalar")]
-pub extern "C" fn nbody_scalar() {
-    let mut nbody = unsafe { get_data() };
-
-    black_box(&mut nbody);
-    for _ in 0..BENCH_TICKS {
-        nbody.tick_seq();
+#[cfg(not(feature = "f64"))]
+impl IntrinBenchmark for NBody {
+    fn run_intrin(&mut self) {
+        let ticks = self.ticks;
+        let mut nbody = black_box(self.benchmark());
+        let _ticks = Region::new("ticks");
+        for _ in 0..ticks {
+            nbody.tick_intrin();
+        }
+        black_box(&mut nbody);
     }
-    black_box(&mut nbody);
 }
 
-#[no_mangle]
-#[cfg(variant="spmd")]
-pub extern "C" fn nbody_spmd() {
-    let mut nbody = unsafe { get_data() };
+#[cfg(not(feature = "f64"))]
+bench! {
+    "nbody" => static NBODY: NBody = NBody {
+        bodies1: &mut [],
+        bodies2: &mut [],
+        ticks: 0,
+    }, intrin;
+}
 
-    black_box(&mut nbody);
-    for _ in 0..BENCH_TICKS {
-        nbody.tick_par();
-    }
-    black_box(&mut nbody);
+#[cfg(feature = "f64")]
+bench! {
+    "nbody" => static NBODY: NBody = NBody {
+        bodies1: &mut [],
+        bodies2: &mut [],
+        ticks: 0,
+    };
 }
@@ -0,0 +1,17 @@
+// All variants of every benchmark, run on the host through the fallbacks of
+// the SPMD functions and the host versions of the `intrin` types.
+#[macro_use]
+extern crate nyuzi_support;
+extern crate nbody;
+
+use nbody::NBody;
+
+#[cfg(not(feature = "f64"))]
+host_tests! {
+    simulation => NBody::new(), sizes: [16, 64], intrin;
+}
+
+#[cfg(feature = "f64")]
+host_tests! {
+    simulation => NBody::new(), sizes: [16, 64];
+}
@@ -1,19 +1,37 @@
-#![feature(core_intrinsics, lang_items)]
+#![feature(lang_items)]
 #![no_std]
 
 #[cfg(feature="link_fib")] extern crate fib;
 #[cfg(feature="link_nbody")] extern crate nbody;
 #[cfg(feature="link_fwt")] extern crate fwt;
+#[cfg(feature="link_mandelbrot")] extern crate mandelbrot;
+#[cfg(feature="link_hash")] extern crate hash;
+#[cfg(feature="link_sgemm")] extern crate sgemm;
+extern crate nyuzi_support;
 
-use core::intrinsics;
+use core::fmt::Write;
+use nyuzi_support::console::Console;
+
+#[cfg(registry)]
+pub mod registry;
+
+/// Exit status after a panic, the same one Rust uses on hosted targets.
+/// bench.py looks for it (and for the message) to tell panics from other
+/// failures.
+pub const PANIC_EXIT_STATUS: i32 = 101;
+
+extern "C" {
+    fn exit(status: i32) -> !;
+}
 
 #[lang = "panic_fmt"]
 #[no_mangle]
-pub extern "C" fn rust_begin_panic(_msg: core::fmt::Arguments,
-                                   _file: &'static str,
-                                   _line: u32)
+pub extern "C" fn rust_begin_panic(msg: core::fmt::Arguments,
+                                   file: &'static str,
+                                   line: u32)
                                    -> ! {
-    unsafe { intrinsics::abort() }
+    let _ = writeln!(Console, "panicked at '{}', {}:{}", msg, file, line);
+    unsafe { exit(PANIC_EXIT_STATUS) }
 }
 
 // Not actually needed on nyuzi b/c panic=abort, but makes my editor plugin happy
@@ -0,0 +1,178 @@
+// With `--cfg registry`, every benchmark and variant of the linked crates is
+// compiled in, and the combined tables of the crates (see `bench!`) let the
+// harness pick one at runtime instead of needing a separate build per
+// benchmark and variant.
+
+use core::fmt::Write;
+use nyuzi_support::benchmark::Size;
+use nyuzi_support::console::Console;
+use nyuzi_support::registry::Entry;
+
+#[cfg(feature="link_fib")]
+const FIB: &'static [Entry] = ::fib::BENCHMARKS;
+#[cfg(not(feature="link_fib"))]
+const FIB: &'static [Entry] = &[];
+
+#[cfg(feature="link_nbody")]
+const NBODY: &'static [Entry] = ::nbody::BENCHMARKS;
+#[cfg(not(feature="link_nbody"))]
+const NBODY: &'static [Entry] = &[];
+
+#[cfg(feature="link_fwt")]
+const FWT: &'static [Entry] = ::fwt::BENCHMARKS;
+#[cfg(not(feature="link_fwt"))]
+const FWT: &'static [Entry] = &[];
+
+#[cfg(feature="link_mandelbrot")]
+const MANDELBROT: &'static [Entry] = ::mandelbrot::BENCHMARKS;
+#[cfg(not(feature="link_mandelbrot"))]
+const MANDELBROT: &'static [Entry] = &[];
+
+#[cfg(feature="link_hash")]
+const HASH: &'static [Entry] = ::hash::BENCHMARKS;
+#[cfg(not(feature="link_hash"))]
+const HASH: &'static [Entry] = &[];
+
+#[cfg(feature="link_sgemm")]
+const SGEMM: &'static [Entry] = ::sgemm::BENCHMARKS;
+#[cfg(not(feature="link_sgemm"))]
+const SGEMM: &'static [Entry] = &[];
+
+static TABLES: [&'static [Entry]; 6] = [FIB, NBODY, FWT, MANDELBROT, HASH, SGEMM];
+
+pub fn len() -> usize {
+    TABLES.iter().map(|table| table.len()).sum()
+}
+
+pub fn get(mut index: usize) -> Option<&'static Entry> {
+    for table in &TABLES {
+        if index < table.len() {
+            return Some(&table[index]);
+        }
+        index -= table.len();
+    }
+    None
+}
+
+/// The index of the entry for which `matches` returns true.
+pub fn position<F>(matches: F) -> Option<usize>
+    where F: Fn(&Entry) -> bool
+{
+    (0..len()).position(|i| matches(get(i).unwrap()))
+}
+
+pub fn find(name: &str, variant: &str) -> Option<usize> {
+    position(|e| e.name() == name && e.variant() == variant)
+}
+
+// C interface, used by harness.cpp when built with BENCH_REGISTRY
+
+/// Compares a NUL terminated C string with a Rust string.
+unsafe fn c_str_eq(mut c: *const u8, s: &str) -> bool {
+    for &b in s.as_bytes() {
+        if *c != b {
+            return false;
+        }
+        c = c.offset(1);
+    }
+    *c == 0
+}
+
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_len() -> usize {
+    len()
+}
+
+/// The benchmark name of entry `index`, or null if there is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_name(index: usize) -> *const u8 {
+    get(index).map_or(0 as *const u8, |e| e.name.as_ptr())
+}
+
+/// The variant of entry `index`, or null if there is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_variant(index: usize) -> *const u8 {
+    get(index).map_or(0 as *const u8, |e| e.variant.as_ptr())
+}
+
+/// The index of the entry with the given name and variant, or -1.
+#[no_mangle]
+pub unsafe extern "C" fn nyuzi_registry_find(name: *const u8, variant: *const u8) -> i32 {
+    position(|e| c_str_eq(name, e.name()) && c_str_eq(variant, e.variant())).map_or(-1, |i| i as i32)
+}
+
+/// Sets up entry `index` for a problem of `n` elements and `steps`
+/// iterations, zero meaning the default (see `Size`). Returns false if the
+/// benchmark does not support that size, or there is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_setup(index: usize, n: usize, steps: usize) -> bool {
+    get(index).map_or(false, |e| e.setup(Size { n: n, steps: steps }))
+}
+
+/// Runs entry `index`. Returns false if there is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_run(index: usize) -> bool {
+    match get(index) {
+        Some(e) => {
+            e.run();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Verifies the outputs of entry `index`. Returns false if they are wrong or
+/// there is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_verify(index: usize) -> bool {
+    get(index).map_or(false, |e| e.verify())
+}
+
+/// Whether the variants of the benchmark of entry `index` give the same
+/// outputs for a problem of the given size, to within `max_ulps` for floats.
+/// False if there is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_equivalent(index: usize,
+                                            n: usize,
+                                            steps: usize,
+                                            max_ulps: u32)
+                                            -> bool {
+    get(index).map_or(false, |e| e.equivalent(Size { n: n, steps: steps }, max_ulps))
+}
+
+/// Measures entry `index` with `warmup` untimed and `runs` timed runs (see
+/// `Entry::measure`), and prints the result as one line
+///
+/// ```text
+/// measure:<name>:<variant>:<warmup>:<runs>:<min>:<median>:<max>
+/// ```
+///
+/// Returns false, printing nothing, if setup fails, `runs` is zero or there
+/// is no such entry.
+#[no_mangle]
+pub extern "C" fn nyuzi_registry_measure(index: usize,
+                                         n: usize,
+                                         steps: usize,
+                                         warmup: usize,
+                                         runs: usize)
+                                         -> bool {
+    let e = match get(index) {
+        Some(e) => e,
+        None => return false,
+    };
+    match e.measure(Size { n: n, steps: steps }, warmup, runs) {
+        Some(stats) => {
+            let _ = writeln!(Console,
+                             "measure:{}:{}:{}:{}:{}:{}:{}",
+                             e.name(),
+                             e.variant(),
+                             warmup,
+                             stats.runs,
+                             stats.min,
+                             stats.median,
+                             stats.max);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,338 @@
+// The stages of a benchmark. `setup` allocates the buffers for a problem size
+// from `pool` and fills them with the inputs, `verify` checks the outputs
+// afterwards. harness.cpp calls both outside the timed region, so only
+// `run_scalar` / `run_spmd` / `run_intrin` are measured.
+//
+// Benchmarks are exported with `bench!`, which keeps each one in a static and
+// generates the functions harness.cpp calls. On the host, `host_tests!` and
+// `host_benches!` run them through the fallbacks of the SPMD functions.
+use core::mem::transmute;
+use linalg::{Vector2, Vector3, Vector4, Point3};
+
+/// The problem size of a run. What the fields mean is up to the benchmark,
+/// zero selects its default.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Size {
+    /// Number of elements: inputs, points, bodies, ...
+    pub n: usize,
+    /// Number of iterations, for benchmarks that have them.
+    pub steps: usize,
+}
+
+pub trait Benchmark {
+    /// The elements of the output, see `equivalent`.
+    type Output: Equivalent;
+
+    /// Allocates the buffers for `size` and fills them with the inputs, so
+    /// every run starts from the same state. Returns false if the benchmark
+    /// does not support `size`, or the pool is exhausted.
+    fn setup(&mut self, size: Size) -> bool;
+
+    fn run_scalar(&mut self);
+
+    fn run_spmd(&mut self);
+
+    /// Checks the outputs of the last run.
+    fn verify(&self) -> bool;
+
+    /// The outputs of the last run.
+    fn output(&self) -> &[Self::Output];
+}
+
+/// Benchmarks that also have a variant vectorised by hand with `intrin`.
+/// `bench!`, `host_tests!` and `host_benches!` only accept `, intrin` for
+/// benchmarks implementing this.
+pub trait IntrinBenchmark: Benchmark {
+    fn run_intrin(&mut self);
+}
+
+/// Runs the scalar variant on `scalar` and `run` (e.g.
+/// `Benchmark::run_spmd`) on `other`, both from the same inputs, and compares
+/// their outputs element by element.
+pub fn equivalent<B, F>(scalar: &mut B, other: &mut B, run: F, size: Size, max_ulps: u32) -> bool
+    where B: Benchmark,
+          F: FnOnce(&mut B)
+{
+    if !scalar.setup(size) || !other.setup(size) {
+        return false;
+    }
+    scalar.run_scalar();
+    run(other);
+
+    let (expected, actual) = (scalar.output(), other.output());
+    expected.len() == actual.len() &&
+    expected.iter().zip(actual.iter()).all(|(e, a)| e.equivalent(a, max_ulps))
+}
+
+/// Results that should not change when a benchmark is vectorised. Integers
+/// must match exactly, floats to within `max_ulps` units in the last place,
+/// since SPMD code may legitimately round differently.
+pub trait Equivalent {
+    fn equivalent(&self, other: &Self, max_ulps: u32) -> bool;
+}
+
+macro_rules! equivalent_exactly {
+    ($($T: ty),*) => {
+        $(
+            impl Equivalent for $T {
+                #[inline]
+                fn equivalent(&self, other: &Self, _: u32) -> bool {
+                    *self == *other
+                }
+            }
+        )*
+    }
+}
+
+equivalent_exactly!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, bool);
+
+/// The distance between `a` and `b` in units in the last place, saturating.
+/// Zero if both are NaN, the maximum if only one is.
+pub fn ulps_f32(a: f32, b: f32) -> u32 {
+    if a != a || b != b {
+        return if a != a && b != b { 0 } else { u32::max_value() };
+    }
+    // Map the sign-magnitude representation onto a monotonic integer scale
+    fn ordered(x: f32) -> i64 {
+        let bits: i32 = unsafe { transmute(x) };
+        if bits < 0 {
+            (i32::min_value() - bits) as i64
+        } else {
+            bits as i64
+        }
+    }
+    let diff = (ordered(a) - ordered(b)).abs();
+    if diff > u32::max_value() as i64 {
+        u32::max_value()
+    } else {
+        diff as u32
+    }
+}
+
+/// As `ulps_f32`.
+pub fn ulps_f64(a: f64, b: f64) -> u32 {
+    if a != a || b != b {
+        return if a != a && b != b { 0 } else { u32::max_value() };
+    }
+    fn ordered(x: f64) -> i64 {
+        let bits: i64 = unsafe { transmute(x) };
+        if bits < 0 {
+            i64::min_value() - bits
+        } else {
+            bits
+        }
+    }
+    let (a, b) = (ordered(a), ordered(b));
+    let diff = if a > b {
+        (a as u64).wrapping_sub(b as u64)
+    } else {
+        (b as u64).wrapping_sub(a as u64)
+    };
+    if diff > u32::max_value() as u64 {
+        u32::max_value()
+    } else {
+        diff as u32
+    }
+}
+
+impl Equivalent for f32 {
+    #[inline]
+    fn equivalent(&self, other: &Self, max_ulps: u32) -> bool {
+        ulps_f32(*self, *other) <= max_ulps
+    }
+}
+
+impl Equivalent for f64 {
+    #[inline]
+    fn equivalent(&self, other: &Self, max_ulps: u32) -> bool {
+        ulps_f64(*self, *other) <= max_ulps
+    }
+}
+
+macro_rules! equivalent_fields {
+    ($($Type: ident { $($field: ident),+ })*) => {
+        $(
+            impl<S: Equivalent> Equivalent for $Type<S> {
+                #[inline]
+                fn equivalent(&self, other: &Self, max_ulps: u32) -> bool {
+                    $(self.$field.equivalent(&other.$field, max_ulps))&&+
+                }
+            }
+        )*
+    }
+}
+
+equivalent_fields! {
+    Vector2 { x, y }
+    Vector3 { x, y, z }
+    Vector4 { x, y, z, w }
+    Point3 { x, y, z }
+}
+
+/// Tests for a benchmark crate, run on the host: all variants must pass
+/// `verify` and give the same outputs, at the default size and at the given
+/// values of `Size::n`. Each benchmark is given as
+///
+/// ```ignore
+/// name => expression creating the benchmark, sizes: [n, ...];
+/// ```
+///
+/// followed by `, intrin` before the semicolon if it implements
+/// `IntrinBenchmark`. The SPMD variants run through the host fallbacks, which
+/// apply the kernel to one element after the other, and the `intrin` types
+/// loop over the lanes, so the outputs must match exactly.
+#[macro_export]
+macro_rules! host_tests {
+    (@munch $name: ident => $bench: expr, sizes: [$($n: expr),*], intrin; $($rest: tt)*) => {
+        host_tests! {
+            @tests $name => $bench, sizes: [$($n),*], {
+                /// Also checks equivalence with the scalar variant.
+                #[test]
+                fn intrin() {
+                    use $crate::benchmark::IntrinBenchmark;
+
+                    for size in sizes() {
+                        let mut bench = $bench;
+                        assert!(bench.setup(size), "{:?}", size);
+                        bench.run_intrin();
+                        assert!(bench.verify(), "{:?}", size);
+                        assert!($crate::benchmark::equivalent(&mut $bench,
+                                                              &mut $bench,
+                                                              IntrinBenchmark::run_intrin,
+                                                              size,
+                                                              0),
+                                "{:?}",
+                                size);
+                    }
+                }
+            }
+        }
+        host_tests!(@munch $($rest)*);
+    };
+    (@munch $name: ident => $bench: expr, sizes: [$($n: expr),*]; $($rest: tt)*) => {
+        host_tests!(@tests $name => $bench, sizes: [$($n),*], {});
+        host_tests!(@munch $($rest)*);
+    };
+    (@munch) => {};
+    (@tests $name: ident => $bench: expr, sizes: [$($n: expr),*], { $($intrin: tt)* }) => {
+        mod $name {
+            use super::*;
+            use $crate::benchmark::{Benchmark, Size};
+
+            fn sizes() -> Vec<Size> {
+                vec![Size::default() $(, Size { n: $n, steps: 0 })*]
+            }
+
+            #[test]
+            fn scalar() {
+                for size in sizes() {
+                    let mut bench = $bench;
+                    assert!(bench.setup(size), "{:?}", size);
+                    bench.run_scalar();
+                    assert!(bench.verify(), "{:?}", size);
+                }
+            }
+
+            #[test]
+            fn spmd() {
+                for size in sizes() {
+                    let mut bench = $bench;
+                    assert!(bench.setup(size), "{:?}", size);
+                    bench.run_spmd();
+                    assert!(bench.verify(), "{:?}", size);
+                }
+            }
+
+            #[test]
+            fn scalar_spmd_equivalent() {
+                for size in sizes() {
+                    assert!($crate::benchmark::equivalent(&mut $bench,
+                                                          &mut $bench,
+                                                          Benchmark::run_spmd,
+                                                          size,
+                                                          0),
+                            "{:?}",
+                            size);
+                }
+            }
+
+            $($intrin)*
+        }
+    };
+    ($($benches: tt)*) => {
+        host_tests!(@munch $($benches)*);
+    };
+}
+
+/// Wall-clock benchmarks of all variants at the default size, run on the
+/// host with `cargo bench`. Each benchmark is given as in `host_tests!`,
+/// without the sizes. Needs `#![feature(test)]` and `extern crate test` in the
+/// crate root. The times include `setup`. Every iteration resets the pool
+/// and creates a new benchmark, so that no buffers outlive the reset.
+#[macro_export]
+macro_rules! host_benches {
+    (@munch $name: ident => $bench: expr, intrin; $($rest: tt)*) => {
+        host_benches! {
+            @benches $name => $bench, {
+                #[bench]
+                fn intrin(b: &mut Bencher) {
+                    use $crate::benchmark::IntrinBenchmark;
+
+                    b.iter(|| {
+                        // Benchmarks run one after the other, and the previous
+                        // iteration's benchmark (with its buffers) is gone by now
+                        unsafe { $crate::pool::reset() };
+                        let mut bench = $bench;
+                        bench.setup(Size::default());
+                        bench.run_intrin();
+                        black_box(bench);
+                    });
+                }
+            }
+        }
+        host_benches!(@munch $($rest)*);
+    };
+    (@munch $name: ident => $bench: expr; $($rest: tt)*) => {
+        host_benches!(@benches $name => $bench, {});
+        host_benches!(@munch $($rest)*);
+    };
+    (@munch) => {};
+    (@benches $name: ident => $bench: expr, { $($intrin: tt)* }) => {
+        mod $name {
+            use super::*;
+            use test::{Bencher, black_box};
+            use $crate::benchmark::{Benchmark, Size};
+
+            #[bench]
+            fn scalar(b: &mut Bencher) {
+                b.iter(|| {
+                    // Benchmarks run one after the other, and the previous
+                    // iteration's benchmark (with its buffers) is gone by now
+                    unsafe { $crate::pool::reset() };
+                    let mut bench = $bench;
+                    bench.setup(Size::default());
+                    bench.run_scalar();
+                    black_box(bench);
+                });
+            }
+
+            #[bench]
+            fn spmd(b: &mut Bencher) {
+                b.iter(|| {
+                    // Benchmarks run one after the other, and the previous
+                    // iteration's benchmark (with its buffers) is gone by now
+                    unsafe { $crate::pool::reset() };
+                    let mut bench = $bench;
+                    bench.setup(Size::default());
+                    bench.run_spmd();
+                    black_box(bench);
+                });
+            }
+
+            $($intrin)*
+        }
+    };
+    ($($benches: tt)*) => {
+        host_benches!(@munch $($benches)*);
+    };
+}
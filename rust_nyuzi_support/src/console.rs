@@ -0,0 +1,30 @@
+// Formatted output through libc's putchar. Unlike printf!, this is type
+// checked and prints anything that implements Display or Debug, so it is
+// what the print! and println! macros use.
+use core::fmt::{self, Write};
+
+mod ffi {
+    extern {
+        pub fn putchar(c: i32) -> i32;
+    }
+}
+
+/// The console as a `fmt::Write` sink. Output is unbuffered.
+pub struct Console;
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            unsafe {
+                ffi::putchar(byte as i32);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    // Writing to the console can't fail
+    let _ = Console.write_fmt(args);
+}
@@ -0,0 +1,265 @@
+// Fixed point numbers for kernels that should stay on the integer pipeline.
+// A value is stored as an i32 with a fixed number of fractional bits:
+// Q16_16 covers +-32768 with a resolution of 1.5e-5, Q8_24 covers +-128 with
+// a resolution of 6e-8.
+//
+// Results that don't fit wrap around, as with the wrapping_* methods of the
+// integer types (in every build, not only in release builds). Multiplication
+// and division go through an i64 intermediate, products are rounded towards
+// negative infinity and quotients towards zero.
+use core::fmt;
+use core::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Neg};
+
+// sin and cos are evaluated on i64s with 30 fractional bits, which leaves
+// enough headroom for the range reduction of any Q16_16 or Q8_24 angle.
+const PI_Q30: i64 = 3373259426;
+const FRAC_PI_2_Q30: i64 = 1686629713;
+const TWO_PI_Q30: i64 = 6746518852;
+
+// 1/3!, 1/5!, ..., 1/11! with 30 fractional bits
+const SIN_COEFFS: [i64; 5] = [178956971, 8947849, 213044, 2959, 27];
+
+#[inline]
+fn mul_q30(a: i64, b: i64) -> i64 {
+    (a * b) >> 30
+}
+
+/// sin with 30 fractional bits for both the angle and the result.
+#[inline]
+fn sin_q30(x: i64) -> i64 {
+    // Reduce to [-pi, pi], then fold onto [-pi/2, pi/2] using
+    // sin(pi - x) = sin(x).
+    let half_turn = if x < 0 { -PI_Q30 } else { PI_Q30 };
+    let mut x = x - (x + half_turn) / TWO_PI_Q30 * TWO_PI_Q30;
+    if x > FRAC_PI_2_Q30 {
+        x = PI_Q30 - x;
+    } else if x < -FRAC_PI_2_Q30 {
+        x = -PI_Q30 - x;
+    }
+
+    // Taylor series up to x^11, in Horner form
+    let x2 = mul_q30(x, x);
+    let mut poly = SIN_COEFFS[4];
+    poly = SIN_COEFFS[3] - mul_q30(poly, x2);
+    poly = SIN_COEFFS[2] - mul_q30(poly, x2);
+    poly = SIN_COEFFS[1] - mul_q30(poly, x2);
+    poly = SIN_COEFFS[0] - mul_q30(poly, x2);
+    poly = (1 << 30) - mul_q30(poly, x2);
+    mul_q30(x, poly)
+}
+
+/// Integer square root, rounded down.
+#[inline]
+fn isqrt(n: u64) -> u64 {
+    let mut op = n;
+    let mut result = 0;
+    let mut one = 1u64 << 62;
+    while one > op {
+        one >>= 2;
+    }
+    while one != 0 {
+        if op >= result + one {
+            op -= result + one;
+            result = (result >> 1) + one;
+        } else {
+            result >>= 1;
+        }
+        one >>= 2;
+    }
+    result
+}
+
+macro_rules! fixed_type {
+    ($Fixed: ident, $frac_bits: expr, pi: $pi: expr, frac_pi_2: $frac_pi_2: expr,
+     two_pi: $two_pi: expr) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+        pub struct $Fixed(i32);
+
+        impl $Fixed {
+            pub const FRAC_BITS: u32 = $frac_bits;
+
+            pub const ZERO: $Fixed = $Fixed(0);
+            pub const ONE: $Fixed = $Fixed(1 << $frac_bits);
+            pub const PI: $Fixed = $Fixed($pi);
+            pub const FRAC_PI_2: $Fixed = $Fixed($frac_pi_2);
+            pub const TWO_PI: $Fixed = $Fixed($two_pi);
+
+            #[inline]
+            pub fn from_bits(bits: i32) -> Self {
+                $Fixed(bits)
+            }
+
+            #[inline]
+            pub fn to_bits(self) -> i32 {
+                self.0
+            }
+
+            #[inline]
+            pub fn from_int(x: i32) -> Self {
+                $Fixed(x.wrapping_shl($frac_bits))
+            }
+
+            /// The integer part, rounded towards negative infinity.
+            #[inline]
+            pub fn to_int(self) -> i32 {
+                self.0 >> $frac_bits
+            }
+
+            #[inline]
+            pub fn from_f32(x: f32) -> Self {
+                $Fixed((x * (1u32 << $frac_bits) as f32) as i32)
+            }
+
+            #[inline]
+            pub fn to_f32(self) -> f32 {
+                self.0 as f32 / (1u32 << $frac_bits) as f32
+            }
+
+            #[inline]
+            pub fn from_f64(x: f64) -> Self {
+                $Fixed((x * (1u32 << $frac_bits) as f64) as i32)
+            }
+
+            #[inline]
+            pub fn to_f64(self) -> f64 {
+                self.0 as f64 / (1u32 << $frac_bits) as f64
+            }
+
+            #[inline]
+            pub fn abs(self) -> Self {
+                $Fixed(self.0.wrapping_abs())
+            }
+
+            #[inline]
+            fn from_q30(x: i64) -> Self {
+                let shift = 30 - $frac_bits;
+                $Fixed(((x + (1 << (shift - 1))) >> shift) as i32)
+            }
+
+            #[inline]
+            fn to_q30(self) -> i64 {
+                (self.0 as i64) << (30 - $frac_bits)
+            }
+
+            #[inline]
+            pub fn sin(self) -> Self {
+                $Fixed::from_q30(sin_q30(self.to_q30()))
+            }
+
+            #[inline]
+            pub fn cos(self) -> Self {
+                $Fixed::from_q30(sin_q30(self.to_q30() + FRAC_PI_2_Q30))
+            }
+
+            /// The square root, rounded down. Negative numbers give zero.
+            #[inline]
+            pub fn sqrt(self) -> Self {
+                if self.0 <= 0 {
+                    return $Fixed(0);
+                }
+                $Fixed(isqrt((self.0 as u64) << $frac_bits) as i32)
+            }
+        }
+
+        impl Add for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                $Fixed(self.0.wrapping_add(rhs.0))
+            }
+        }
+
+        impl AddAssign for $Fixed {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 = self.0.wrapping_add(rhs.0);
+            }
+        }
+
+        impl Sub for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                $Fixed(self.0.wrapping_sub(rhs.0))
+            }
+        }
+
+        impl SubAssign for $Fixed {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 = self.0.wrapping_sub(rhs.0);
+            }
+        }
+
+        impl Mul for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: Self) -> Self {
+                $Fixed(((self.0 as i64 * rhs.0 as i64) >> $frac_bits) as i32)
+            }
+        }
+
+        impl MulAssign for $Fixed {
+            #[inline]
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl Mul<i32> for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: i32) -> Self {
+                $Fixed(self.0.wrapping_mul(rhs))
+            }
+        }
+
+        impl Div for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: Self) -> Self {
+                $Fixed((((self.0 as i64) << $frac_bits) / rhs.0 as i64) as i32)
+            }
+        }
+
+        impl DivAssign for $Fixed {
+            #[inline]
+            fn div_assign(&mut self, rhs: Self) {
+                *self = *self / rhs;
+            }
+        }
+
+        impl Div<i32> for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: i32) -> Self {
+                $Fixed(self.0.wrapping_div(rhs))
+            }
+        }
+
+        impl Neg for $Fixed {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self {
+                $Fixed(self.0.wrapping_neg())
+            }
+        }
+
+        impl fmt::Debug for $Fixed {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, concat!(stringify!($Fixed), "({:?})"), self.to_f64())
+            }
+        }
+    }
+}
+
+fixed_type!(Q16_16, 16, pi: 205887, frac_pi_2: 102944, two_pi: 411775);
+fixed_type!(Q8_24, 24, pi: 52707179, frac_pi_2: 26353589, two_pi: 105414357);
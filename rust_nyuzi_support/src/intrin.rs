@@ -0,0 +1,587 @@
+// Nyuzi's native vectors of 16 lanes, for hand-vectorised (`intrin`)
+// variants of benchmarks: the counterparts of the vecf16_t, veci16_t and
+// vecu16_t types and the __builtin_nyuzi_* functions the C++ benchmarks use.
+//
+// Arithmetic and bitwise operations work lane by lane through the operator
+// traits, integers wrap on overflow. Comparisons give a `Mask` with one bit
+// per lane, which selects between the lanes of two vectors. Memory is
+// accessed with gather and scatter, so slices need no particular alignment.
+//
+// On Nyuzi the types are SIMD vectors and most operations single
+// instructions (integer division is not, the hardware has none). Host builds
+// loop over the lanes instead, with the same results, so that `intrin`
+// variants can be tested on the host.
+use core::mem::transmute;
+use core::ops::{Add, Sub, Mul, Div, Rem, Neg, BitAnd, BitOr, BitXor, Not, Shl, Shr};
+
+pub const LANES: usize = 16;
+
+#[cfg(target_arch="nyuzi")]
+extern "platform-intrinsic" {
+    fn simd_add<T>(x: T, y: T) -> T;
+    fn simd_sub<T>(x: T, y: T) -> T;
+    fn simd_mul<T>(x: T, y: T) -> T;
+    fn simd_div<T>(x: T, y: T) -> T;
+    fn simd_shl<T>(x: T, y: T) -> T;
+    fn simd_shr<T>(x: T, y: T) -> T;
+    fn simd_and<T>(x: T, y: T) -> T;
+    fn simd_or<T>(x: T, y: T) -> T;
+    fn simd_xor<T>(x: T, y: T) -> T;
+    fn simd_cast<T, U>(x: T) -> U;
+}
+
+// The LLVM intrinsics behind the __builtin_nyuzi_* functions. Pointers are
+// passed as vectors of 32 bit addresses, masks as integers.
+#[cfg(target_arch="nyuzi")]
+#[allow(improper_ctypes)]
+extern "C" {
+    #[link_name = "llvm.nyuzi.mask.cmpi.eq"]
+    fn mask_cmpi_eq(x: I32x16, y: I32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.ne"]
+    fn mask_cmpi_ne(x: I32x16, y: I32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.slt"]
+    fn mask_cmpi_slt(x: I32x16, y: I32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.sle"]
+    fn mask_cmpi_sle(x: I32x16, y: I32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.sgt"]
+    fn mask_cmpi_sgt(x: I32x16, y: I32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.sge"]
+    fn mask_cmpi_sge(x: I32x16, y: I32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.ult"]
+    fn mask_cmpi_ult(x: U32x16, y: U32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.ule"]
+    fn mask_cmpi_ule(x: U32x16, y: U32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.ugt"]
+    fn mask_cmpi_ugt(x: U32x16, y: U32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpi.uge"]
+    fn mask_cmpi_uge(x: U32x16, y: U32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpf.eq"]
+    fn mask_cmpf_eq(x: F32x16, y: F32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpf.ne"]
+    fn mask_cmpf_ne(x: F32x16, y: F32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpf.lt"]
+    fn mask_cmpf_lt(x: F32x16, y: F32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpf.le"]
+    fn mask_cmpf_le(x: F32x16, y: F32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpf.gt"]
+    fn mask_cmpf_gt(x: F32x16, y: F32x16) -> u32;
+    #[link_name = "llvm.nyuzi.mask.cmpf.ge"]
+    fn mask_cmpf_ge(x: F32x16, y: F32x16) -> u32;
+    #[link_name = "llvm.nyuzi.vector.mixi"]
+    fn vector_mixi(mask: u32, x: I32x16, y: I32x16) -> I32x16;
+    #[link_name = "llvm.nyuzi.vector.mixf"]
+    fn vector_mixf(mask: u32, x: F32x16, y: F32x16) -> F32x16;
+    #[link_name = "llvm.nyuzi.gather.loadi"]
+    fn gather_loadi(ptrs: U32x16) -> I32x16;
+    #[link_name = "llvm.nyuzi.gather.loadf"]
+    fn gather_loadf(ptrs: U32x16) -> F32x16;
+    #[link_name = "llvm.nyuzi.scatter.storei"]
+    fn scatter_storei(ptrs: U32x16, values: I32x16);
+    #[link_name = "llvm.nyuzi.scatter.storef"]
+    fn scatter_storef(ptrs: U32x16, values: F32x16);
+}
+
+macro_rules! vector_types {
+    ($($Vector: ident($T: ty);)*) => {
+        $(
+            #[cfg(target_arch="nyuzi")]
+            #[repr(simd)]
+            #[derive(Copy, Clone, Debug)]
+            pub struct $Vector($T, $T, $T, $T, $T, $T, $T, $T,
+                               $T, $T, $T, $T, $T, $T, $T, $T);
+
+            #[cfg(not(target_arch="nyuzi"))]
+            #[derive(Copy, Clone, Debug)]
+            pub struct $Vector([$T; LANES]);
+
+            impl $Vector {
+                /// All lanes set to `x`.
+                #[inline]
+                pub fn splat(x: $T) -> Self {
+                    $Vector::from_array([x; LANES])
+                }
+
+                #[inline]
+                pub fn from_array(lanes: [$T; LANES]) -> Self {
+                    unsafe { transmute(lanes) }
+                }
+
+                #[inline]
+                pub fn to_array(self) -> [$T; LANES] {
+                    unsafe { transmute(self) }
+                }
+
+                #[inline]
+                pub fn extract(self, lane: usize) -> $T {
+                    self.to_array()[lane]
+                }
+
+                /// Applies `f` to one lane after the other, for operations
+                /// without a vector instruction (e.g. square roots).
+                #[inline]
+                pub fn map<F: Fn($T) -> $T>(self, f: F) -> Self {
+                    let mut lanes = self.to_array();
+                    for x in &mut lanes {
+                        *x = f(*x);
+                    }
+                    $Vector::from_array(lanes)
+                }
+
+                /// `xs[start..start + LANES]`.
+                #[inline]
+                pub fn load(xs: &[$T], start: usize) -> Self {
+                    $Vector::gather(xs, U32x16::splat(start as u32) + U32x16::lane_ids())
+                }
+
+                /// Stores the lanes to `xs[start..start + LANES]`.
+                #[inline]
+                pub fn store(self, xs: &mut [$T], start: usize) {
+                    self.scatter(xs, U32x16::splat(start as u32) + U32x16::lane_ids())
+                }
+
+                /// Lane `i` is `xs[indices[i]]`. Panics if an index is out of
+                /// bounds.
+                #[inline]
+                pub fn gather(xs: &[$T], indices: U32x16) -> Self {
+                    assert!(indices.lt(U32x16::splat(xs.len() as u32)).all());
+                    unsafe { $Vector::gather_unchecked(xs.as_ptr(), indices) }
+                }
+
+                /// Stores lane `i` to `xs[indices[i]]`, in lane order if
+                /// indices repeat. Panics if an index is out of bounds.
+                #[inline]
+                pub fn scatter(self, xs: &mut [$T], indices: U32x16) {
+                    assert!(indices.lt(U32x16::splat(xs.len() as u32)).all());
+                    unsafe { self.scatter_unchecked(xs.as_mut_ptr(), indices) }
+                }
+
+                #[cfg(not(target_arch="nyuzi"))]
+                pub unsafe fn gather_unchecked(base: *const $T, indices: U32x16) -> Self {
+                    let indices = indices.to_array();
+                    let mut lanes = [<$T as Default>::default(); LANES];
+                    for (x, &i) in lanes.iter_mut().zip(indices.iter()) {
+                        *x = *base.offset(i as isize);
+                    }
+                    $Vector::from_array(lanes)
+                }
+
+                #[cfg(not(target_arch="nyuzi"))]
+                pub unsafe fn scatter_unchecked(self, base: *mut $T, indices: U32x16) {
+                    for (&x, &i) in self.to_array().iter().zip(indices.to_array().iter()) {
+                        *base.offset(i as isize) = x;
+                    }
+                }
+            }
+        )*
+    }
+}
+
+vector_types! {
+    I32x16(i32);
+    U32x16(u32);
+    F32x16(f32);
+}
+
+// The addresses of `base[indices[i]]`
+#[cfg(target_arch="nyuzi")]
+#[inline]
+fn addresses<T>(base: *const T, indices: U32x16) -> U32x16 {
+    U32x16::splat(base as u32) + indices * U32x16::splat(::core::mem::size_of::<T>() as u32)
+}
+
+#[cfg(target_arch="nyuzi")]
+impl I32x16 {
+    #[inline]
+    pub unsafe fn gather_unchecked(base: *const i32, indices: U32x16) -> Self {
+        gather_loadi(addresses(base, indices))
+    }
+
+    #[inline]
+    pub unsafe fn scatter_unchecked(self, base: *mut i32, indices: U32x16) {
+        scatter_storei(addresses(base, indices), self)
+    }
+}
+
+#[cfg(target_arch="nyuzi")]
+impl U32x16 {
+    #[inline]
+    pub unsafe fn gather_unchecked(base: *const u32, indices: U32x16) -> Self {
+        I32x16::gather_unchecked(base as *const i32, indices).as_u32()
+    }
+
+    #[inline]
+    pub unsafe fn scatter_unchecked(self, base: *mut u32, indices: U32x16) {
+        self.as_i32().scatter_unchecked(base as *mut i32, indices)
+    }
+}
+
+#[cfg(target_arch="nyuzi")]
+impl F32x16 {
+    #[inline]
+    pub unsafe fn gather_unchecked(base: *const f32, indices: U32x16) -> Self {
+        gather_loadf(addresses(base, indices))
+    }
+
+    #[inline]
+    pub unsafe fn scatter_unchecked(self, base: *mut f32, indices: U32x16) {
+        scatter_storef(addresses(base, indices), self)
+    }
+}
+
+macro_rules! binary_ops {
+    ($Vector: ident { $($Trait: ident, $method: ident, $intrinsic: ident,
+                        |$x: ident, $y: ident| $lane: expr;)* }) => {
+        $(
+            impl $Trait for $Vector {
+                type Output = $Vector;
+
+                #[cfg(target_arch="nyuzi")]
+                #[inline]
+                fn $method(self, other: $Vector) -> $Vector {
+                    unsafe { $intrinsic(self, other) }
+                }
+
+                #[cfg(not(target_arch="nyuzi"))]
+                fn $method(self, other: $Vector) -> $Vector {
+                    let (xs, ys) = (self.to_array(), other.to_array());
+                    let mut lanes = xs;
+                    for i in 0..LANES {
+                        let ($x, $y) = (xs[i], ys[i]);
+                        lanes[i] = $lane;
+                    }
+                    $Vector::from_array(lanes)
+                }
+            }
+        )*
+    }
+}
+
+binary_ops!(I32x16 {
+    Add, add, simd_add, |x, y| x.wrapping_add(y);
+    Sub, sub, simd_sub, |x, y| x.wrapping_sub(y);
+    Mul, mul, simd_mul, |x, y| x.wrapping_mul(y);
+    Div, div, simd_div, |x, y| x / y;
+    BitAnd, bitand, simd_and, |x, y| x & y;
+    BitOr, bitor, simd_or, |x, y| x | y;
+    BitXor, bitxor, simd_xor, |x, y| x ^ y;
+});
+
+binary_ops!(U32x16 {
+    Add, add, simd_add, |x, y| x.wrapping_add(y);
+    Sub, sub, simd_sub, |x, y| x.wrapping_sub(y);
+    Mul, mul, simd_mul, |x, y| x.wrapping_mul(y);
+    Div, div, simd_div, |x, y| x / y;
+    BitAnd, bitand, simd_and, |x, y| x & y;
+    BitOr, bitor, simd_or, |x, y| x | y;
+    BitXor, bitxor, simd_xor, |x, y| x ^ y;
+});
+
+binary_ops!(F32x16 {
+    Add, add, simd_add, |x, y| x + y;
+    Sub, sub, simd_sub, |x, y| x - y;
+    Mul, mul, simd_mul, |x, y| x * y;
+    Div, div, simd_div, |x, y| x / y;
+});
+
+macro_rules! integer_ops {
+    ($($Vector: ident($T: ty);)*) => {
+        $(
+            impl Rem for $Vector {
+                type Output = $Vector;
+
+                #[inline]
+                fn rem(self, other: $Vector) -> $Vector {
+                    self - self / other * other
+                }
+            }
+
+            impl Not for $Vector {
+                type Output = $Vector;
+
+                #[inline]
+                fn not(self) -> $Vector {
+                    self ^ $Vector::splat(!0)
+                }
+            }
+
+            /// Shifts all lanes by `n` bits.
+            impl Shl<u32> for $Vector {
+                type Output = $Vector;
+
+                #[cfg(target_arch="nyuzi")]
+                #[inline]
+                fn shl(self, n: u32) -> $Vector {
+                    unsafe { simd_shl(self, $Vector::splat(n as $T)) }
+                }
+
+                #[cfg(not(target_arch="nyuzi"))]
+                fn shl(self, n: u32) -> $Vector {
+                    self.map(|x| x.wrapping_shl(n))
+                }
+            }
+
+            /// Shifts all lanes by `n` bits, arithmetically for signed lanes.
+            impl Shr<u32> for $Vector {
+                type Output = $Vector;
+
+                #[cfg(target_arch="nyuzi")]
+                #[inline]
+                fn shr(self, n: u32) -> $Vector {
+                    unsafe { simd_shr(self, $Vector::splat(n as $T)) }
+                }
+
+                #[cfg(not(target_arch="nyuzi"))]
+                fn shr(self, n: u32) -> $Vector {
+                    self.map(|x| x.wrapping_shr(n))
+                }
+            }
+        )*
+    }
+}
+
+integer_ops! {
+    I32x16(i32);
+    U32x16(u32);
+}
+
+impl Neg for F32x16 {
+    type Output = F32x16;
+
+    #[inline]
+    fn neg(self) -> F32x16 {
+        // Unlike 0.0 - x, this gets the sign of zeros right
+        F32x16::splat(-0.0) - self
+    }
+}
+
+macro_rules! comparisons {
+    ($Vector: ident { $($method: ident, $intrinsic: ident, $op: tt;)* }) => {
+        impl $Vector {
+            $(
+                #[cfg(target_arch="nyuzi")]
+                #[inline]
+                pub fn $method(self, other: $Vector) -> Mask {
+                    Mask(unsafe { $intrinsic(self, other) })
+                }
+
+                #[cfg(not(target_arch="nyuzi"))]
+                pub fn $method(self, other: $Vector) -> Mask {
+                    let (xs, ys) = (self.to_array(), other.to_array());
+                    Mask((0..LANES).fold(0, |m, i| if xs[i] $op ys[i] { m | 1 << i } else { m }))
+                }
+            )*
+        }
+    }
+}
+
+comparisons!(I32x16 {
+    eq, mask_cmpi_eq, ==;
+    ne, mask_cmpi_ne, !=;
+    lt, mask_cmpi_slt, <;
+    le, mask_cmpi_sle, <=;
+    gt, mask_cmpi_sgt, >;
+    ge, mask_cmpi_sge, >=;
+});
+
+comparisons!(U32x16 {
+    lt, mask_cmpi_ult, <;
+    le, mask_cmpi_ule, <=;
+    gt, mask_cmpi_ugt, >;
+    ge, mask_cmpi_uge, >=;
+});
+
+comparisons!(F32x16 {
+    eq, mask_cmpf_eq, ==;
+    ne, mask_cmpf_ne, !=;
+    lt, mask_cmpf_lt, <;
+    le, mask_cmpf_le, <=;
+    gt, mask_cmpf_gt, >;
+    ge, mask_cmpf_ge, >=;
+});
+
+impl U32x16 {
+    /// 0, 1, ..., 15: the lane numbers, like `spmd_lane_id` in SPMD code.
+    #[inline]
+    pub fn lane_ids() -> Self {
+        U32x16::from_array([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15])
+    }
+
+    // Signedness does not matter for (in)equality
+    #[inline]
+    pub fn eq(self, other: U32x16) -> Mask {
+        self.as_i32().eq(other.as_i32())
+    }
+
+    #[inline]
+    pub fn ne(self, other: U32x16) -> Mask {
+        self.as_i32().ne(other.as_i32())
+    }
+
+    /// The same bits, as signed lanes.
+    #[inline]
+    pub fn as_i32(self) -> I32x16 {
+        unsafe { transmute(self) }
+    }
+}
+
+impl I32x16 {
+    /// The same bits, as unsigned lanes.
+    #[inline]
+    pub fn as_u32(self) -> U32x16 {
+        unsafe { transmute(self) }
+    }
+
+    #[cfg(target_arch="nyuzi")]
+    #[inline]
+    pub fn to_f32(self) -> F32x16 {
+        unsafe { simd_cast(self) }
+    }
+
+    #[cfg(not(target_arch="nyuzi"))]
+    pub fn to_f32(self) -> F32x16 {
+        let mut lanes = [0.0; LANES];
+        for (y, &x) in lanes.iter_mut().zip(self.to_array().iter()) {
+            *y = x as f32;
+        }
+        F32x16::from_array(lanes)
+    }
+}
+
+impl F32x16 {
+    /// Rounds towards zero, like `as i32`.
+    #[cfg(target_arch="nyuzi")]
+    #[inline]
+    pub fn to_i32(self) -> I32x16 {
+        unsafe { simd_cast(self) }
+    }
+
+    #[cfg(not(target_arch="nyuzi"))]
+    pub fn to_i32(self) -> I32x16 {
+        let mut lanes = [0; LANES];
+        for (y, &x) in lanes.iter_mut().zip(self.to_array().iter()) {
+            *y = x as i32;
+        }
+        I32x16::from_array(lanes)
+    }
+}
+
+/// One bit per lane, the result of comparing vectors. The order of the bits
+/// is the hardware's, so masks are only built by comparisons and combined
+/// with the bitwise operators.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Mask(u32);
+
+const ALL_LANES: u32 = (1 << LANES) - 1;
+
+impl Mask {
+    /// All lanes set.
+    #[inline]
+    pub fn full() -> Self {
+        Mask(ALL_LANES)
+    }
+
+    /// No lanes set.
+    #[inline]
+    pub fn empty() -> Self {
+        Mask(0)
+    }
+
+    #[inline]
+    pub fn all(self) -> bool {
+        self.0 == ALL_LANES
+    }
+
+    #[inline]
+    pub fn any(self) -> bool {
+        self.0 != 0
+    }
+
+    #[inline]
+    pub fn none(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The lanes of `x` where the mask is set, those of `y` elsewhere.
+    #[inline]
+    pub fn select<V: Select>(self, x: V, y: V) -> V {
+        V::select(self, x, y)
+    }
+}
+
+impl BitAnd for Mask {
+    type Output = Mask;
+
+    #[inline]
+    fn bitand(self, other: Mask) -> Mask {
+        Mask(self.0 & other.0)
+    }
+}
+
+impl BitOr for Mask {
+    type Output = Mask;
+
+    #[inline]
+    fn bitor(self, other: Mask) -> Mask {
+        Mask(self.0 | other.0)
+    }
+}
+
+impl Not for Mask {
+    type Output = Mask;
+
+    #[inline]
+    fn not(self) -> Mask {
+        Mask(!self.0 & ALL_LANES)
+    }
+}
+
+/// Vectors whose lanes a `Mask` can select.
+pub trait Select: Copy {
+    fn select(mask: Mask, x: Self, y: Self) -> Self;
+}
+
+#[cfg(not(target_arch="nyuzi"))]
+macro_rules! select_lanes {
+    ($mask: expr, $x: expr, $y: expr, $Vector: ident) => {{
+        let (mut lanes, ys) = ($x.to_array(), $y.to_array());
+        for i in 0..LANES {
+            if $mask.0 & (1 << i) == 0 {
+                lanes[i] = ys[i];
+            }
+        }
+        $Vector::from_array(lanes)
+    }}
+}
+
+impl Select for I32x16 {
+    #[cfg(target_arch="nyuzi")]
+    #[inline]
+    fn select(mask: Mask, x: Self, y: Self) -> Self {
+        unsafe { vector_mixi(mask.0, x, y) }
+    }
+
+    #[cfg(not(target_arch="nyuzi"))]
+    fn select(mask: Mask, x: Self, y: Self) -> Self {
+        select_lanes!(mask, x, y, I32x16)
+    }
+}
+
+impl Select for U32x16 {
+    #[inline]
+    fn select(mask: Mask, x: Self, y: Self) -> Self {
+        I32x16::select(mask, x.as_i32(), y.as_i32()).as_u32()
+    }
+}
+
+impl Select for F32x16 {
+    #[cfg(target_arch="nyuzi")]
+    #[inline]
+    fn select(mask: Mask, x: Self, y: Self) -> Self {
+        unsafe { vector_mixf(mask.0, x, y) }
+    }
+
+    #[cfg(not(target_arch="nyuzi"))]
+    fn select(mask: Mask, x: Self, y: Self) -> Self {
+        select_lanes!(mask, x, y, F32x16)
+    }
+}
@@ -1,4 +1,5 @@
-#![feature(core_intrinsics, lang_items, asm, linkage)]
+#![feature(core_intrinsics, lang_items, asm, linkage, repr_simd, platform_intrinsics,
+           link_llvm_intrinsics)]
 #![no_std]
 
 #[allow(unused_imports)]
@@ -35,25 +36,29 @@ pub fn run_vector<T, F>(scratch: &mut [T], f: F)
 fn spmd<T, F>(x: &mut [T], kernel: F)
     where F: Sync + Fn(&mut T)
 {
-    struct KernelData<'a, T: 'a, F>(&'a mut [T], F);
+    struct KernelData<'a, T: 'a, F>(usize, &'a mut [T], F);
 
     fn kernel_wrapper<T, F>(kernel_data: *mut u8)
         where F: Sync + Fn(&mut T)
     {
         unsafe {
             let kernel_data = &mut *(kernel_data as *mut KernelData<T, F>);
-            let elems: &mut [T] = &mut kernel_data.0;
-            let id = intrinsics::spmd_lane_id();
-            (kernel_data.1)(&mut elems[id]);
+            let elems: &mut [T] = &mut kernel_data.1;
+            let id = kernel_data.0 + intrinsics::spmd_lane_id();
+            (kernel_data.2)(&mut elems[id]);
         }
     }
 
-    assert_eq!(x.len(), 16); // TODO
+    assert_eq!(x.len() % 16, 0);
 
-    let mut kernel_data = KernelData(x, kernel);
-    unsafe {
-        intrinsics::spmd_call(kernel_wrapper::<T, F>,
-                              &mut kernel_data as *mut _ as *mut u8);
+    let len = x.len();
+    let mut kernel_data = KernelData(0, x, kernel);
+    while kernel_data.0 < len {
+        unsafe {
+            intrinsics::spmd_call(kernel_wrapper::<T, F>,
+                                  &mut kernel_data as *mut _ as *mut u8);
+        }
+        kernel_data.0 += 16;
     }
 }
 
@@ -85,7 +90,13 @@ pub fn spmd_range<F>(x: Range<usize>, kernel: F)
 }
 
 #[cfg(not(target_arch="nyuzi"))]
-pub fn spmd_range<F>(_: Range<usize>, _: F) {}
+pub fn spmd_range<F>(x: Range<usize>, kernel: F)
+    where F: Fn(usize)
+{
+    for id in x {
+        kernel(id);
+    }
+}
 
 #[cfg(target_arch="nyuzi")]
 pub fn spmd_zip2<T, F>(outs: &mut [T], ins: &[T], kernel: F)
@@ -137,108 +148,32 @@ fn spmd<T, F>(x: &mut [T], kernel: F)
     run_scalar(x, kernel);
 }
 
-#[cfg(not(rust_trig))]
-mod math {
-    // Bindings to libm, using wrapper functions for safety
-    mod ffi {
-        extern {
-            pub fn sinf(x: f32) -> f32;
-            pub fn cosf(x: f32) -> f32;
-            pub fn sqrtf(x: f32) -> f32;
-        }
-    }
+pub mod math;
+pub mod linalg;
+pub mod fixed;
+pub mod intrin;
+pub mod console;
+pub mod timer;
+pub mod perf;
+pub mod measure;
+pub mod pool;
+pub mod benchmark;
+pub mod registry;
 
-    pub fn sin(x: f32) -> f32 {
-        unsafe { ffi::sinf(x) }
-    }
-
-    pub fn cos(x: f32) -> f32 {
-        unsafe { ffi::cosf(x) }
-    }
+pub use math::{sin, cos, tan, atan2, exp, log, pow, sqrt, floor, ceil, fabs};
 
-    pub fn sqrt(x: f32) -> f32 {
-        unsafe { ffi::sqrtf(x) }
-    }
+#[macro_export]
+macro_rules! print {
+    ($($arg: tt)*) => ($crate::console::_print(format_args!($($arg)*)));
 }
-#[cfg(rust_trig)]
-mod math {
-    // Port of parts of Jeff Bush's libm for Nyuzi, to allow
-    // Rust code to have vectorized trig functions available
-    use core::f32::consts::PI;
-
-    #[inline]
-    fn fmod(val1: f32, val2: f32) -> f32 {
-        let whole = (val1 / val2) as i32;
-        return val1 - (whole as f32 * val2);
-    }
-
-    const NUM_TERMS: usize = 6;
-
-    const DENOMINATORS: [f32; NUM_TERMS] = [
-        -0.166666666666667,  // 1 / 3!
-        0.008333333333333,   // 1 / 5!
-        -0.000198412698413,  // 1 / 7!
-        0.000002755731922,   // 1 / 9!
-        -2.50521084e-8,      // 1 / 11!
-        1.6059044e-10        // 1 / 13!
-    ];
-
-    #[inline]
-    #[allow(non_snake_case)]
-    pub fn sin(mut angle: f32) -> f32 {
-        // The approximation begins to diverge past 0-pi/2. To prevent
-        // discontinuities, mirror or flip this function for the remaining
-        // parts of the function.
-        angle = fmod(angle, PI * 2.);
-        let mut resultSign: i32;
-        if angle < 0. {
-            resultSign = -1;
-        } else {
-            resultSign = 1;
-        }
-
-        if angle < 0.0 {
-            angle = -angle;
-        };
-        if angle > PI * 3. / 2. {
-            angle = PI * 2. - angle;
-            resultSign = -resultSign;
-        } else if angle > PI {
-            angle -= PI;
-            resultSign = -resultSign;
-        } else if angle > PI / 2. {
-            angle = PI - angle;
-        }
-
-        let angleSquared = angle * angle;
-        let mut numerator = angle;
-        let mut result = angle;
-
-        for denom in &DENOMINATORS {
-            numerator *= angleSquared;
-            result += numerator * denom;
-        }
 
-        return result * resultSign as f32;
-    }
-
-    #[inline]
-    pub fn cos(angle: f32) -> f32 {
-        sin(angle + PI * 0.5)
-    }
-
-    #[inline]
-    pub fn sqrt(value: f32) -> f32 {
-        let mut guess = value;
-        for _ in 0..10 {
-            guess = ((value / guess) + guess) / 2.0;
-        }
-        guess
-    }
+#[macro_export]
+macro_rules! println {
+    () => (print!("\n"));
+    ($fmt: expr) => (print!(concat!($fmt, "\n")));
+    ($fmt: expr, $($arg: tt)*) => (print!(concat!($fmt, "\n"), $($arg)*));
 }
 
-pub use math::{sin, cos, sqrt};
-
 #[macro_export]
 macro_rules! printf {
     ($fmt: expr) => (printf!($fmt, /* no arguments */));
@@ -0,0 +1,410 @@
+// Small vectors and matrices for benchmarks, modelled on the parts of cgmath
+// that the ported benchmarks use. Everything is plain `Copy` data with
+// inlinable operators, so it lowers to straight-line code inside SPMD kernels.
+//
+// The types are generic over the scalar so that benchmarks can be built in
+// either precision. Matrices are column major, as in cgmath. Vectors and
+// points are repr(C), so their fields can also be accessed as consecutive
+// scalars.
+use core::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Neg};
+
+/// The scalar types vectors and matrices can be built from.
+pub trait Scalar: Copy + PartialEq + PartialOrd +
+    Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> +
+    Div<Output = Self> + Neg<Output = Self> +
+    AddAssign + SubAssign + MulAssign + DivAssign
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn sqrt(self) -> Self;
+}
+
+impl Scalar for f32 {
+    #[inline]
+    fn zero() -> Self {
+        0.0
+    }
+
+    #[inline]
+    fn one() -> Self {
+        1.0
+    }
+
+    #[inline]
+    fn sqrt(self) -> Self {
+        ::math::sqrt(self)
+    }
+}
+
+impl Scalar for f64 {
+    #[inline]
+    fn zero() -> Self {
+        0.0
+    }
+
+    #[inline]
+    fn one() -> Self {
+        1.0
+    }
+
+    #[inline]
+    fn sqrt(self) -> Self {
+        ::math::f64::sqrt(self)
+    }
+}
+
+macro_rules! impl_vector {
+    ($Vector: ident { $($field: ident),+ }) => {
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        #[repr(C)]
+        pub struct $Vector<S> {
+            $(pub $field: S),+
+        }
+
+        impl<S: Scalar> $Vector<S> {
+            #[inline]
+            pub fn new($($field: S),+) -> Self {
+                $Vector { $($field: $field),+ }
+            }
+
+            #[inline]
+            pub fn zero() -> Self {
+                $Vector { $($field: S::zero()),+ }
+            }
+
+            #[inline]
+            pub fn dot(self, other: Self) -> S {
+                S::zero() $(+ self.$field * other.$field)+
+            }
+
+            #[inline]
+            pub fn magnitude2(self) -> S {
+                self.dot(self)
+            }
+
+            #[inline]
+            pub fn magnitude(self) -> S {
+                self.magnitude2().sqrt()
+            }
+
+            /// The vector scaled to length one. Zero vectors give NaNs.
+            #[inline]
+            pub fn normalize(self) -> Self {
+                self / self.magnitude()
+            }
+
+            /// Linear interpolation, `amount` 0 gives `self` and 1 gives `other`.
+            #[inline]
+            pub fn lerp(self, other: Self, amount: S) -> Self {
+                self + (other - self) * amount
+            }
+        }
+
+        impl<S: Scalar> Add for $Vector<S> {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                $Vector { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl<S: Scalar> AddAssign for $Vector<S> {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) {
+                $(self.$field += rhs.$field;)+
+            }
+        }
+
+        impl<S: Scalar> Sub for $Vector<S> {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                $Vector { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl<S: Scalar> SubAssign for $Vector<S> {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) {
+                $(self.$field -= rhs.$field;)+
+            }
+        }
+
+        impl<S: Scalar> Mul<S> for $Vector<S> {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: S) -> Self {
+                $Vector { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl<S: Scalar> MulAssign<S> for $Vector<S> {
+            #[inline]
+            fn mul_assign(&mut self, rhs: S) {
+                $(self.$field *= rhs;)+
+            }
+        }
+
+        impl<S: Scalar> Div<S> for $Vector<S> {
+            type Output = Self;
+
+            #[inline]
+            fn div(self, rhs: S) -> Self {
+                $Vector { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl<S: Scalar> DivAssign<S> for $Vector<S> {
+            #[inline]
+            fn div_assign(&mut self, rhs: S) {
+                $(self.$field /= rhs;)+
+            }
+        }
+
+        impl<S: Scalar> Neg for $Vector<S> {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self {
+                $Vector { $($field: -self.$field),+ }
+            }
+        }
+    }
+}
+
+impl_vector!(Vector2 { x, y });
+impl_vector!(Vector3 { x, y, z });
+impl_vector!(Vector4 { x, y, z, w });
+
+impl<S: Scalar> Vector3<S> {
+    #[inline]
+    pub fn cross(self, other: Self) -> Self {
+        Vector3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    #[inline]
+    pub fn extend(self, w: S) -> Vector4<S> {
+        Vector4::new(self.x, self.y, self.z, w)
+    }
+}
+
+impl<S: Scalar> Vector4<S> {
+    #[inline]
+    pub fn truncate(self) -> Vector3<S> {
+        Vector3::new(self.x, self.y, self.z)
+    }
+}
+
+/// A position, as opposed to a displacement. The difference of two points is
+/// a vector, and only vectors can be added to points.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(C)]
+pub struct Point3<S> {
+    pub x: S,
+    pub y: S,
+    pub z: S,
+}
+
+impl<S: Scalar> Point3<S> {
+    #[inline]
+    pub fn new(x: S, y: S, z: S) -> Self {
+        Point3 { x: x, y: y, z: z }
+    }
+
+    #[inline]
+    pub fn origin() -> Self {
+        Point3::new(S::zero(), S::zero(), S::zero())
+    }
+
+    #[inline]
+    pub fn from_vec(v: Vector3<S>) -> Self {
+        Point3::new(v.x, v.y, v.z)
+    }
+
+    #[inline]
+    pub fn to_vec(self) -> Vector3<S> {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    #[inline]
+    pub fn lerp(self, other: Self, amount: S) -> Self {
+        self + (other - self) * amount
+    }
+}
+
+impl<S: Scalar> Sub for Point3<S> {
+    type Output = Vector3<S>;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Vector3<S> {
+        self.to_vec() - rhs.to_vec()
+    }
+}
+
+impl<S: Scalar> Add<Vector3<S>> for Point3<S> {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Vector3<S>) -> Self {
+        Point3::from_vec(self.to_vec() + rhs)
+    }
+}
+
+impl<S: Scalar> AddAssign<Vector3<S>> for Point3<S> {
+    #[inline]
+    fn add_assign(&mut self, rhs: Vector3<S>) {
+        *self = *self + rhs;
+    }
+}
+
+impl<S: Scalar> Sub<Vector3<S>> for Point3<S> {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Vector3<S>) -> Self {
+        Point3::from_vec(self.to_vec() - rhs)
+    }
+}
+
+impl<S: Scalar> SubAssign<Vector3<S>> for Point3<S> {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Vector3<S>) {
+        *self = *self - rhs;
+    }
+}
+
+macro_rules! impl_matrix {
+    ($Matrix: ident, $Vector: ident { $($col: ident: $index: expr),+ }) => {
+        /// A column major matrix, `x`, `y`, ... are the columns.
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        pub struct $Matrix<S> {
+            $(pub $col: $Vector<S>),+
+        }
+
+        impl<S: Scalar> $Matrix<S> {
+            #[inline]
+            pub fn from_cols($($col: $Vector<S>),+) -> Self {
+                $Matrix { $($col: $col),+ }
+            }
+
+            #[inline]
+            pub fn zero() -> Self {
+                $Matrix { $($col: $Vector::zero()),+ }
+            }
+
+            /// Row `i` of the matrix.
+            #[inline]
+            pub fn row(&self, i: usize) -> $Vector<S> {
+                $Vector { $($col: self.$col.get(i)),+ }
+            }
+
+            #[inline]
+            pub fn transpose(&self) -> Self {
+                $Matrix { $($col: self.row($index)),+ }
+            }
+        }
+
+        impl<S: Scalar> $Vector<S> {
+            /// Component `i`, in declaration order.
+            #[inline]
+            fn get(&self, i: usize) -> S {
+                [$(self.$col),+][i]
+            }
+        }
+
+        impl<S: Scalar> Mul<$Vector<S>> for $Matrix<S> {
+            type Output = $Vector<S>;
+
+            #[inline]
+            fn mul(self, rhs: $Vector<S>) -> $Vector<S> {
+                $Vector::zero() $(+ self.$col * rhs.$col)+
+            }
+        }
+
+        impl<S: Scalar> Mul for $Matrix<S> {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: Self) -> Self {
+                $Matrix { $($col: self * rhs.$col),+ }
+            }
+        }
+
+        impl<S: Scalar> Mul<S> for $Matrix<S> {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: S) -> Self {
+                $Matrix { $($col: self.$col * rhs),+ }
+            }
+        }
+
+        impl<S: Scalar> Add for $Matrix<S> {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                $Matrix { $($col: self.$col + rhs.$col),+ }
+            }
+        }
+
+        impl<S: Scalar> Sub for $Matrix<S> {
+            type Output = Self;
+
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                $Matrix { $($col: self.$col - rhs.$col),+ }
+            }
+        }
+    }
+}
+
+impl_matrix!(Matrix3, Vector3 { x: 0, y: 1, z: 2 });
+impl_matrix!(Matrix4, Vector4 { x: 0, y: 1, z: 2, w: 3 });
+
+impl<S: Scalar> Matrix3<S> {
+    #[inline]
+    pub fn identity() -> Self {
+        let (o, l) = (S::zero(), S::one());
+        Matrix3::from_cols(Vector3::new(l, o, o), Vector3::new(o, l, o), Vector3::new(o, o, l))
+    }
+}
+
+impl<S: Scalar> Matrix4<S> {
+    #[inline]
+    pub fn identity() -> Self {
+        let (o, l) = (S::zero(), S::one());
+        Matrix4::from_cols(Vector4::new(l, o, o, o),
+                           Vector4::new(o, l, o, o),
+                           Vector4::new(o, o, l, o),
+                           Vector4::new(o, o, o, l))
+    }
+
+    /// A translation by `v`.
+    #[inline]
+    pub fn from_translation(v: Vector3<S>) -> Self {
+        let mut m = Matrix4::identity();
+        m.w = v.extend(S::one());
+        m
+    }
+
+    /// Applies the matrix to a point, including the projective divide.
+    #[inline]
+    pub fn transform_point(&self, p: Point3<S>) -> Point3<S> {
+        let v = *self * p.to_vec().extend(S::one());
+        Point3::from_vec(v.truncate() / v.w)
+    }
+
+    /// Applies the matrix to a direction, ignoring the translation.
+    #[inline]
+    pub fn transform_vector(&self, v: Vector3<S>) -> Vector3<S> {
+        (*self * v.extend(S::zero())).truncate()
+    }
+}
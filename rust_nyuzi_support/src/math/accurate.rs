@@ -0,0 +1,148 @@
+// Port of parts of Jeff Bush's libm for Nyuzi, to allow
+// Rust code to have vectorized trig functions available.
+// This is the accurate tier, see fast.rs for the cheaper one and
+// branchless.rs for the same functions without branches.
+use core::f32::consts::PI;
+use core::f32::{INFINITY, NEG_INFINITY, NAN};
+use super::kernels::{CODY_WAITE_LIMIT, NUM_TERMS, copysign, reduce_cody_waite, reduce_large};
+use super::kernels::{sin_kernel, cos_kernel, atan_kernel, exp_kernel, log_kernel, sqrt_kernel};
+
+pub use super::kernels::fabs;
+
+#[inline]
+fn reduce(angle: f32) -> (f32, i32) {
+    if fabs(angle) < CODY_WAITE_LIMIT {
+        reduce_cody_waite(angle)
+    } else {
+        reduce_large(angle)
+    }
+}
+
+#[inline]
+pub fn sin(angle: f32) -> f32 {
+    // Evaluating both series is cheaper than lanes diverging over the quadrant
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    let result = if q & 1 == 0 { s } else { c };
+    if q & 2 == 0 { result } else { -result }
+}
+
+#[inline]
+pub fn cos(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    let result = if q & 1 == 0 { c } else { s };
+    if (q + 1) & 2 == 0 { result } else { -result }
+}
+
+#[inline]
+pub fn tan(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    if q & 1 == 0 { s / c } else { -c / s }
+}
+
+#[inline]
+fn atan(x: f32) -> f32 {
+    // Reduce to |x| <= tan(pi/8) using atan(x) = pi/2 - atan(1/x) and
+    // atan(x) = pi/4 + atan((x - 1) / (x + 1)).
+    let ax = fabs(x);
+    let (offset, t) = if ax > 2.414213562373095 {
+        (PI * 0.5, -1.0 / ax)
+    } else if ax > 0.4142135623730950 {
+        (PI * 0.25, (ax - 1.0) / (ax + 1.0))
+    } else {
+        (0.0, ax)
+    };
+    copysign(offset + atan_kernel(t), x)
+}
+
+#[inline]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    if x == 0.0 {
+        if y == 0.0 {
+            return 0.0;
+        }
+        return copysign(PI * 0.5, y);
+    }
+
+    let result = atan(y / x);
+    if x < 0.0 {
+        // atan(y / x) is off by half a turn in the left half plane
+        if y < 0.0 { result - PI } else { result + PI }
+    } else {
+        result
+    }
+}
+
+#[inline]
+pub fn exp(x: f32) -> f32 {
+    if x != x {
+        return x;
+    } else if x > 88.72283905206835 {
+        return INFINITY;
+    } else if x < -103.97208 {
+        return 0.0;
+    }
+    exp_kernel(x)
+}
+
+#[inline]
+pub fn log(x: f32) -> f32 {
+    if x != x || x == INFINITY {
+        return x;
+    } else if x < 0.0 {
+        return NAN;
+    } else if x == 0.0 {
+        return NEG_INFINITY;
+    }
+    log_kernel(x)
+}
+
+#[inline]
+pub fn pow(x: f32, y: f32) -> f32 {
+    if y == 0.0 {
+        return 1.0;
+    }
+
+    let result = exp(y * log(fabs(x)));
+    if x < 0.0 {
+        // A negative base only has a real power for integral exponents
+        if floor(y) != y {
+            return NAN;
+        }
+        let half = y * 0.5;
+        if floor(half) != half {
+            return -result;
+        }
+    }
+    result
+}
+
+#[inline]
+pub fn sqrt(value: f32) -> f32 {
+    if !(value > 0.0 && value < INFINITY) {
+        return if value < 0.0 { NAN } else { value };
+    }
+    sqrt_kernel(value)
+}
+
+#[inline]
+pub fn floor(x: f32) -> f32 {
+    // Anything this large is already integral (or inf/NaN) and would
+    // overflow the conversion below.
+    if !(fabs(x) < 8388608.0) {
+        return x;
+    }
+    let truncated = x as i32 as f32;
+    if truncated > x {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+#[inline]
+pub fn ceil(x: f32) -> f32 {
+    -floor(-x)
+}
@@ -0,0 +1,116 @@
+// The accurate tier without branches: every special case and quadrant is
+// computed unconditionally and the result picked with `select`, so SPMD lanes
+// never diverge. The arithmetic is the same as in accurate.rs, which makes the
+// results bit-identical and the two directly comparable in benchmarks.
+//
+// The one exception is the Payne-Hanek reduction for angles beyond
+// CODY_WAITE_LIMIT. Doing it unconditionally would cost far more than the
+// divergence it avoids, and no benchmark gets anywhere near that range.
+use core::f32::consts::PI;
+use core::f32::{INFINITY, NEG_INFINITY, NAN};
+use super::kernels::{CODY_WAITE_LIMIT, NUM_TERMS, copysign, select, floor_small};
+use super::kernels::{reduce_cody_waite, reduce_large};
+use super::kernels::{sin_kernel, cos_kernel, atan_kernel, exp_kernel, log_kernel, sqrt_kernel};
+
+pub use super::kernels::fabs;
+
+#[inline]
+fn reduce(angle: f32) -> (f32, i32) {
+    if fabs(angle) < CODY_WAITE_LIMIT {
+        reduce_cody_waite(angle)
+    } else {
+        reduce_large(angle)
+    }
+}
+
+#[inline]
+fn flip_sign(x: f32, flip: bool) -> f32 {
+    select(flip, -x, x)
+}
+
+#[inline]
+pub fn sin(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    flip_sign(select(q & 1 == 0, s, c), q & 2 != 0)
+}
+
+#[inline]
+pub fn cos(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    flip_sign(select(q & 1 == 0, c, s), (q + 1) & 2 != 0)
+}
+
+#[inline]
+pub fn tan(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    select(q & 1 == 0, s / c, -c / s)
+}
+
+#[inline]
+fn atan(x: f32) -> f32 {
+    let ax = fabs(x);
+    let large = ax > 2.414213562373095;
+    let medium = ax > 0.4142135623730950;
+    let offset = select(large, PI * 0.5, select(medium, PI * 0.25, 0.0));
+    let t = select(large, -1.0 / ax, select(medium, (ax - 1.0) / (ax + 1.0), ax));
+    copysign(offset + atan_kernel(t), x)
+}
+
+#[inline]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    let result = atan(y / x);
+    let result = select(x < 0.0, select(y < 0.0, result - PI, result + PI), result);
+    let on_axis = select(y == 0.0, 0.0, copysign(PI * 0.5, y));
+    select(x == 0.0, on_axis, result)
+}
+
+#[inline]
+pub fn exp(x: f32) -> f32 {
+    // Clamp first, out of range inputs would overflow the conversions in the
+    // kernel. NaN compares false and is clamped as well.
+    let in_range = x <= 88.72283905206835 && x >= -103.97208;
+    let result = exp_kernel(select(in_range, x, 0.0));
+    let result = select(x < -103.97208, 0.0, result);
+    let result = select(x > 88.72283905206835, INFINITY, result);
+    select(x != x, x, result)
+}
+
+#[inline]
+pub fn log(x: f32) -> f32 {
+    let in_range = x > 0.0 && x < INFINITY;
+    let result = log_kernel(select(in_range, x, 1.0));
+    let result = select(x == 0.0, NEG_INFINITY, result);
+    let result = select(x < 0.0, NAN, result);
+    select(x != x || x == INFINITY, x, result)
+}
+
+#[inline]
+pub fn pow(x: f32, y: f32) -> f32 {
+    let result = exp(y * log(fabs(x)));
+    let half = y * 0.5;
+    let negative = x < 0.0;
+    let result = select(negative && floor(half) != half, -result, result);
+    let result = select(negative && floor(y) != y, NAN, result);
+    select(y == 0.0, 1.0, result)
+}
+
+#[inline]
+pub fn sqrt(value: f32) -> f32 {
+    let in_range = value > 0.0 && value < INFINITY;
+    let result = sqrt_kernel(select(in_range, value, 1.0));
+    select(in_range, result, select(value < 0.0, NAN, value))
+}
+
+#[inline]
+pub fn floor(x: f32) -> f32 {
+    let in_range = fabs(x) < 8388608.0;
+    select(in_range, floor_small(select(in_range, x, 0.0)), x)
+}
+
+#[inline]
+pub fn ceil(x: f32) -> f32 {
+    -floor(-x)
+}
@@ -0,0 +1,448 @@
+// Double precision counterpart of the accurate tier. Nyuzi has no double
+// precision hardware, so all of this ends up as soft-float calls; the point is
+// to see how SPMD lowering copes with that, not to be fast.
+//
+// sin/cos/tan use the same scheme as the f32 version (Cody-Waite reduction,
+// with a Payne-Hanek fallback for huge angles, and Taylor series on
+// [-pi/4, pi/4]), with more terms.
+// exp, log and atan use the polynomials from fdlibm.
+use core::f64::consts::{PI, FRAC_PI_2, FRAC_2_PI};
+use core::f64::{INFINITY, NEG_INFINITY, NAN};
+use core::mem::transmute;
+
+#[inline]
+fn to_bits(x: f64) -> u64 {
+    unsafe { transmute(x) }
+}
+
+#[inline]
+fn from_bits(x: u64) -> f64 {
+    unsafe { transmute(x) }
+}
+
+// Each part of pi/2 has 33 significant bits, so q * part is exact for
+// q < 2^20, i.e. for angles up to about 1.6e6. Larger angles go through the
+// Payne-Hanek reduction instead, see kernels::reduce_large for the f32
+// version.
+
+const CODY_WAITE_LIMIT: f64 = 1.6e6;
+
+const PI_2_PART1: f64 = 1.57079632673412561417e+00;
+const PI_2_PART2: f64 = 6.07710050630396597660e-11;
+const PI_2_PART3: f64 = 2.02226624871116645580e-21;
+const PI_2_PART3_TAIL: f64 = 8.47842766036889956997e-32;
+
+// The first 1216 bits of 2/pi, enough for the largest finite angles
+const TWO_OVER_PI: [u32; 38] = [
+    0xa2f9836e, 0x4e441529, 0xfc2757d1, 0xf534ddc0, 0xdb629599, 0x3c439041,
+    0xfe5163ab, 0xdebbc561, 0xb7246e3a, 0x424dd2e0, 0x06492eea, 0x09d1921c,
+    0xfe1deb1c, 0xb129a73e, 0xe88235f5, 0x2ebb4484, 0xe99c7026, 0xb45f7e41,
+    0x3991d639, 0x835339f4, 0x9c845f8b, 0xbdf9283b, 0x1ff897ff, 0xde05980f,
+    0xef2f118b, 0x5a0a6d1f, 0x6d367ecf, 0x27cb09b7, 0x4f463f66, 0x9e5fea2d,
+    0x7527bac7, 0xebe5f17b, 0x3d0739f7, 0x8a5292ea, 0x6bfb5fb1, 0x1f8d5d08,
+    0x56033046, 0xfc7b6bab
+];
+
+/// angle = r + q * pi/2 with r in [-pi/4, pi/4], for finite angles. Only the
+/// low two bits of q are significant.
+#[inline]
+fn reduce(angle: f64) -> (f64, i32) {
+    if !(fabs(angle) < CODY_WAITE_LIMIT) {
+        return reduce_large(angle);
+    }
+    let q = (angle * FRAC_2_PI + copysign(0.5, angle)) as i64;
+    let qf = q as f64;
+    let r = angle - qf * PI_2_PART1 - qf * PI_2_PART2 - qf * PI_2_PART3 - qf * PI_2_PART3_TAIL;
+    (r, q as i32)
+}
+
+#[inline(never)]
+fn reduce_large(angle: f64) -> (f64, i32) {
+    // |angle| = m * 2^e, where m is a 53 bit integer. As in the f32 version,
+    // only the bits of 2/pi from e - 1 on affect angle * 2/pi mod 4. A window
+    // of 192 of them leaves far more fraction bits than even the angles
+    // closest to a multiple of pi/2 need.
+    let bits = to_bits(angle);
+    let m = (bits & 0xfffffffffffff) | 0x10000000000000;
+    let e = ((bits >> 52) & 0x7ff) as i32 - 1075;
+    let m = [m & 0xffffffff, m >> 32];
+    let mut w = [0u64; 6];
+    for (i, w) in w.iter_mut().enumerate() {
+        *w = two_over_pi_bits(e - 1 + 32 * (5 - i as i32)) as u64;
+    }
+
+    // m * w modulo 2^192 in 32 bit words, least significant first, which is
+    // (angle * 2/pi mod 4) * 2^190
+    let mut p = [0u64; 6];
+    for j in 0..2 {
+        let mut carry = 0;
+        for i in 0..6 - j {
+            let t = w[i] * m[j] + p[i + j] + carry;
+            p[i + j] = t & 0xffffffff;
+            carry = t >> 32;
+        }
+    }
+
+    // The top two bits are the integer part, the rest is the fraction. A
+    // fraction >= 0.5 rounds q up, reinterpreting it as signed does the
+    // matching subtraction of 1. The top 128 bits of the fraction are plenty.
+    let top = p[5] as u32;
+    let q = ((top >> 30) + (top >> 29 & 1)) as i32;
+    let hi = (p[5] << 34) | (p[4] << 2) | (p[3] >> 30);
+    let lo = (p[3] << 34) | (p[2] << 2) | (p[1] >> 30);
+    let negative = (hi as i64) < 0;
+    let (hi, lo) = if negative {
+        let lo = (!lo).wrapping_add(1);
+        ((!hi).wrapping_add((lo == 0) as u64), lo)
+    } else {
+        (hi, lo)
+    };
+    let scale = 1.0 / 18446744073709551616.0;
+    let r = (hi as f64 + lo as f64 * scale) * scale * FRAC_PI_2;
+    let r = if negative { -r } else { r };
+
+    if angle < 0.0 { (-r, -q) } else { (r, q) }
+}
+
+/// Bits `i` to `i + 31` of 2/pi, where bit 1 is the first one after the point
+/// and the bits before it are zero. Unlike for f32, `i` can be negative (down
+/// to -63) for angles just above CODY_WAITE_LIMIT.
+#[inline]
+fn two_over_pi_bits(i: i32) -> u32 {
+    fn word(index: i32) -> u64 {
+        if index < 0 {
+            0
+        } else {
+            TWO_OVER_PI[index as usize] as u64
+        }
+    }
+    let start = i - 1 + 64;
+    let (index, shift) = (start / 32 - 2, start % 32);
+    let window = (word(index) << 32) | word(index + 1);
+    (window << shift >> 32) as u32
+}
+
+const NUM_TERMS: usize = 10;
+
+const DENOMINATORS: [f64; NUM_TERMS] = [
+    -0.16666666666666666,     // 1 / 3!
+    0.008333333333333333,     // 1 / 5!
+    -0.0001984126984126984,   // 1 / 7!
+    2.7557319223985893e-06,   // 1 / 9!
+    -2.505210838544172e-08,   // 1 / 11!
+    1.6059043836821613e-10,   // 1 / 13!
+    -7.647163731819816e-13,   // 1 / 15!
+    2.8114572543455206e-15,   // 1 / 17!
+    -8.22063524662433e-18,    // 1 / 19!
+    1.9572941063391263e-20    // 1 / 21!
+];
+
+const COS_DENOMINATORS: [f64; NUM_TERMS] = [
+    -0.5,                     // 1 / 2!
+    0.041666666666666664,     // 1 / 4!
+    -0.001388888888888889,    // 1 / 6!
+    2.48015873015873e-05,     // 1 / 8!
+    -2.755731922398589e-07,   // 1 / 10!
+    2.08767569878681e-09,     // 1 / 12!
+    -1.1470745597729725e-11,  // 1 / 14!
+    4.779477332387385e-14,    // 1 / 16!
+    -1.5619206968586225e-16,  // 1 / 18!
+    4.110317623312165e-19     // 1 / 20!
+];
+
+#[inline]
+fn sin_kernel(angle: f64) -> f64 {
+    let angle_squared = angle * angle;
+    let mut numerator = angle;
+    let mut result = angle;
+
+    for denom in &DENOMINATORS {
+        numerator *= angle_squared;
+        result += numerator * denom;
+    }
+
+    result
+}
+
+#[inline]
+fn cos_kernel(angle: f64) -> f64 {
+    let angle_squared = angle * angle;
+    let mut numerator = 1.0;
+    let mut result = 1.0;
+
+    for denom in &COS_DENOMINATORS {
+        numerator *= angle_squared;
+        result += numerator * denom;
+    }
+
+    result
+}
+
+#[inline]
+pub fn sin(angle: f64) -> f64 {
+    if !(fabs(angle) < INFINITY) {
+        return angle - angle;
+    }
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r), cos_kernel(r));
+    let result = if q & 1 == 0 { s } else { c };
+    if q & 2 == 0 { result } else { -result }
+}
+
+#[inline]
+pub fn cos(angle: f64) -> f64 {
+    if !(fabs(angle) < INFINITY) {
+        return angle - angle;
+    }
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r), cos_kernel(r));
+    let result = if q & 1 == 0 { c } else { s };
+    if (q + 1) & 2 == 0 { result } else { -result }
+}
+
+#[inline]
+pub fn tan(angle: f64) -> f64 {
+    if !(fabs(angle) < INFINITY) {
+        return angle - angle;
+    }
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r), cos_kernel(r));
+    if q & 1 == 0 { s / c } else { -c / s }
+}
+
+const ATAN_HI: [f64; 4] = [
+    4.63647609000806093515e-01,  // atan(0.5)
+    7.85398163397448278999e-01,  // atan(1.0)
+    9.82793723247329054082e-01,  // atan(1.5)
+    1.57079632679489655800e+00   // atan(inf)
+];
+
+const ATAN_LO: [f64; 4] = [
+    2.26987774529616870924e-17,
+    3.06161699786838301793e-17,
+    1.39033110312309984516e-17,
+    6.12323399573676603587e-17
+];
+
+const ATAN_COEFFS: [f64; 11] = [
+    3.33333333333329318027e-01,
+    -1.99999999998764832476e-01,
+    1.42857142725034663711e-01,
+    -1.11111104054623557880e-01,
+    9.09088713343650656196e-02,
+    -7.69187620504482999495e-02,
+    6.66107313738753120669e-02,
+    -5.83357013379057348645e-02,
+    4.97687799461593236017e-02,
+    -3.65315727442169155270e-02,
+    1.62858201153657823623e-02
+];
+
+#[inline]
+fn atan(x: f64) -> f64 {
+    // Reduce to a small interval around 0, 0.5, 1, 1.5 or infinity
+    let ax = fabs(x);
+    let (id, t) = if ax < 0.4375 {
+        (None, ax)
+    } else if ax < 0.6875 {
+        (Some(0), (2.0 * ax - 1.0) / (2.0 + ax))
+    } else if ax < 1.1875 {
+        (Some(1), (ax - 1.0) / (ax + 1.0))
+    } else if ax < 2.4375 {
+        (Some(2), (ax - 1.5) / (1.0 + 1.5 * ax))
+    } else {
+        (Some(3), -1.0 / ax)
+    };
+
+    let a = &ATAN_COEFFS;
+    let z = t * t;
+    let w = z * z;
+    let s1 = z * (a[0] + w * (a[2] + w * (a[4] + w * (a[6] + w * (a[8] + w * a[10])))));
+    let s2 = w * (a[1] + w * (a[3] + w * (a[5] + w * (a[7] + w * a[9]))));
+    let result = match id {
+        None => t - t * (s1 + s2),
+        Some(i) => ATAN_HI[i] - ((t * (s1 + s2) - ATAN_LO[i]) - t),
+    };
+    copysign(result, x)
+}
+
+#[inline]
+pub fn atan2(y: f64, x: f64) -> f64 {
+    if x == 0.0 {
+        if y == 0.0 {
+            return 0.0;
+        }
+        return copysign(PI * 0.5, y);
+    }
+
+    let result = atan(y / x);
+    if x < 0.0 {
+        // atan(y / x) is off by half a turn in the left half plane
+        if y < 0.0 { result - PI } else { result + PI }
+    } else {
+        result
+    }
+}
+
+const LN2_HI: f64 = 6.93147180369123816490e-01;
+const LN2_LO: f64 = 1.90821492927058770002e-10;
+
+#[inline]
+pub fn exp(x: f64) -> f64 {
+    if x != x {
+        return x;
+    } else if x > 7.09782712893383973096e+02 {
+        return INFINITY;
+    } else if x < -7.45133219101941108420e+02 {
+        return 0.0;
+    }
+
+    // exp(x) = 2^k * exp(r) with |r| <= ln(2) / 2, and exp(r) from a rational
+    // approximation of r * (exp(r) + 1) / (exp(r) - 1).
+    let k = floor(x * 1.44269504088896338700e+00 + 0.5);
+    let hi = x - k * LN2_HI;
+    let lo = k * LN2_LO;
+    let r = hi - lo;
+
+    let t = r * r;
+    let c = r - t * (1.66666666666666019037e-01 +
+                     t * (-2.77777777770155933842e-03 +
+                          t * (6.61375632143793436117e-05 +
+                               t * (-1.65339022054652515390e-06 +
+                                    t * 4.13813679705723846039e-08))));
+    let y = 1.0 - ((lo - (r * c) / (2.0 - c)) - hi);
+    ldexp(y, k as i32)
+}
+
+#[inline]
+pub fn log(x: f64) -> f64 {
+    if x != x || x == INFINITY {
+        return x;
+    } else if x < 0.0 {
+        return NAN;
+    } else if x == 0.0 {
+        return NEG_INFINITY;
+    }
+
+    // x = m * 2^k with m in [sqrt(1/2), sqrt(2)), then
+    // log(m) = 2 * atanh(s) with s = (m - 1) / (m + 1)
+    let (m, mut k) = frexp(x);
+    let m = if m < 0.70710678118654752440 {
+        k -= 1;
+        m + m
+    } else {
+        m
+    };
+    let k = k as f64;
+
+    let f = m - 1.0;
+    let s = f / (2.0 + f);
+    let z = s * s;
+    let w = z * z;
+    let t1 = w * (3.999999999940941908e-01 +
+                  w * (2.222219843214978396e-01 + w * 1.531383769920937332e-01));
+    let t2 = z * (6.666666666666735130e-01 +
+                  w * (2.857142874366239149e-01 +
+                       w * (1.818357216161805012e-01 + w * 1.479819860511658591e-01)));
+    let r = t2 + t1;
+    let half_f_squared = 0.5 * f * f;
+    k * LN2_HI - ((half_f_squared - (s * (half_f_squared + r) + k * LN2_LO)) - f)
+}
+
+#[inline]
+pub fn pow(x: f64, y: f64) -> f64 {
+    if y == 0.0 {
+        return 1.0;
+    }
+
+    let result = exp(y * log(fabs(x)));
+    if x < 0.0 {
+        // A negative base only has a real power for integral exponents
+        if floor(y) != y {
+            return NAN;
+        }
+        let half = y * 0.5;
+        if floor(half) != half {
+            return -result;
+        }
+    }
+    result
+}
+
+#[inline]
+pub fn sqrt(value: f64) -> f64 {
+    if !(value > 0.0 && value < INFINITY) {
+        return if value < 0.0 { NAN } else { value };
+    }
+
+    // Subnormals don't have the implicit leading one the seed below relies
+    // on, so scale them up by 2^54 first (as frexp does) and the root back
+    // down by 2^27.
+    let (value, scale) = if value < 2.2250738585072014e-308 {
+        (value * 18014398509481984.0, 7.450580596923828e-9)
+    } else {
+        (value, 1.0)
+    };
+
+    // Halving the exponent gets within 6% of the root, four Newton steps take
+    // that to full precision.
+    let mut guess = from_bits((to_bits(value) >> 1) + 0x1ff7a3bea91d9b1b);
+    for _ in 0..4 {
+        guess = ((value / guess) + guess) / 2.0;
+    }
+    guess * scale
+}
+
+#[inline]
+pub fn floor(x: f64) -> f64 {
+    // Anything this large is already integral (or inf/NaN) and would
+    // overflow the conversion below.
+    if !(fabs(x) < 4503599627370496.0) {
+        return x;
+    }
+    let truncated = x as i64 as f64;
+    if truncated > x {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+#[inline]
+pub fn ceil(x: f64) -> f64 {
+    -floor(-x)
+}
+
+#[inline]
+pub fn fabs(x: f64) -> f64 {
+    from_bits(to_bits(x) & 0x7fffffffffffffff)
+}
+
+#[inline]
+fn copysign(x: f64, sign: f64) -> f64 {
+    from_bits((to_bits(x) & 0x7fffffffffffffff) | (to_bits(sign) & 0x8000000000000000))
+}
+
+/// Splits a finite, positive `x` into `m * 2^e` with `m` in [0.5, 1).
+#[inline]
+fn frexp(x: f64) -> (f64, i32) {
+    // Subnormals don't have the implicit leading one, so scale them up first
+    let (x, bias) = if x < 2.2250738585072014e-308 {
+        (x * 18014398509481984.0, -54)
+    } else {
+        (x, 0)
+    };
+    let bits = to_bits(x);
+    let e = ((bits >> 52) & 0x7ff) as i32 - 1022;
+    (from_bits((bits & 0x800fffffffffffff) | 0x3fe0000000000000), e + bias)
+}
+
+/// Computes `x * 2^n` for `n` in the range produced by `exp`.
+#[inline]
+fn ldexp(x: f64, n: i32) -> f64 {
+    // Scale in two steps so that results in the subnormal range don't need
+    // an unrepresentable power of two.
+    let n1 = n / 2;
+    let n2 = n - n1;
+    x * from_bits(((n1 + 1023) as u64) << 52) * from_bits(((n2 + 1023) as u64) << 52)
+}
@@ -0,0 +1,61 @@
+// Bindings to the double precision half of libm, using wrapper functions for
+// safety
+mod ffi {
+    extern {
+        pub fn sin(x: f64) -> f64;
+        pub fn cos(x: f64) -> f64;
+        pub fn tan(x: f64) -> f64;
+        pub fn atan2(y: f64, x: f64) -> f64;
+        pub fn exp(x: f64) -> f64;
+        pub fn log(x: f64) -> f64;
+        pub fn pow(x: f64, y: f64) -> f64;
+        pub fn sqrt(x: f64) -> f64;
+        pub fn floor(x: f64) -> f64;
+        pub fn ceil(x: f64) -> f64;
+        pub fn fabs(x: f64) -> f64;
+    }
+}
+
+pub fn sin(x: f64) -> f64 {
+    unsafe { ffi::sin(x) }
+}
+
+pub fn cos(x: f64) -> f64 {
+    unsafe { ffi::cos(x) }
+}
+
+pub fn tan(x: f64) -> f64 {
+    unsafe { ffi::tan(x) }
+}
+
+pub fn atan2(y: f64, x: f64) -> f64 {
+    unsafe { ffi::atan2(y, x) }
+}
+
+pub fn exp(x: f64) -> f64 {
+    unsafe { ffi::exp(x) }
+}
+
+pub fn log(x: f64) -> f64 {
+    unsafe { ffi::log(x) }
+}
+
+pub fn pow(x: f64, y: f64) -> f64 {
+    unsafe { ffi::pow(x, y) }
+}
+
+pub fn sqrt(x: f64) -> f64 {
+    unsafe { ffi::sqrt(x) }
+}
+
+pub fn floor(x: f64) -> f64 {
+    unsafe { ffi::floor(x) }
+}
+
+pub fn ceil(x: f64) -> f64 {
+    unsafe { ffi::ceil(x) }
+}
+
+pub fn fabs(x: f64) -> f64 {
+    unsafe { ffi::fabs(x) }
+}
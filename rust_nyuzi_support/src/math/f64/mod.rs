@@ -0,0 +1,12 @@
+// Double precision versions of the math functions. There is only one Rust
+// tier here: `math-rust`, `math-fast` and `math-branchless` all select the
+// accurate port, the fast and branchless variants don't exist for f64.
+
+pub mod libm;
+pub mod accurate;
+
+#[cfg(not(any(feature = "math-rust", feature = "math-fast", feature = "math-branchless")))]
+pub use self::libm::*;
+
+#[cfg(any(feature = "math-rust", feature = "math-fast", feature = "math-branchless"))]
+pub use self::accurate::*;
@@ -0,0 +1,58 @@
+// The fast tier of the Rust math port. It is built from the same kernels as
+// the accurate tier, but does less work:
+//
+// - sin/cos/tan only use three Taylor terms, and the range reduction has no
+//   slow path for huge angles (they just lose accuracy).
+// - sqrt starts from a reciprocal square root estimate and refines it twice,
+//   instead of dividing in every Newton step. Zero, infinity and negative
+//   inputs are not special-cased.
+//
+// The remaining functions are the same as in the accurate tier.
+use super::kernels::{from_bits, to_bits, quadrant, sin_kernel, cos_kernel};
+use super::kernels::{PI_2_PART1, PI_2_PART2, PI_2_PART3};
+
+pub use super::accurate::{atan2, exp, log, pow, floor, ceil, fabs};
+
+const NUM_TERMS: usize = 3;
+
+#[inline]
+fn reduce(angle: f32) -> (f32, i32) {
+    let q = quadrant(angle);
+    let qf = q as f32;
+    (angle - qf * PI_2_PART1 - qf * PI_2_PART2 - qf * PI_2_PART3, q)
+}
+
+#[inline]
+pub fn sin(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    let result = if q & 1 == 0 { s } else { c };
+    if q & 2 == 0 { result } else { -result }
+}
+
+#[inline]
+pub fn cos(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    let result = if q & 1 == 0 { c } else { s };
+    if (q + 1) & 2 == 0 { result } else { -result }
+}
+
+#[inline]
+pub fn tan(angle: f32) -> f32 {
+    let (r, q) = reduce(angle);
+    let (s, c) = (sin_kernel(r, NUM_TERMS), cos_kernel(r, NUM_TERMS));
+    if q & 1 == 0 { s / c } else { -c / s }
+}
+
+#[inline]
+pub fn sqrt(value: f32) -> f32 {
+    // The well known 0x5f3759df estimate of 1/sqrt(value), then two
+    // multiplication-only Newton steps for 1/sqrt, then sqrt(x) = x / sqrt(x).
+    let half = value * 0.5;
+    let mut estimate = from_bits(0x5f3759df - (to_bits(value) >> 1));
+    for _ in 0..2 {
+        estimate *= 1.5 - half * estimate * estimate;
+    }
+    value * estimate
+}
@@ -0,0 +1,271 @@
+// Building blocks shared by the precision tiers of the Rust math port. Nothing
+// in here branches (apart from the Payne-Hanek reduction for huge angles), so
+// the tiers differ only in how they handle special cases and select results.
+use core::f32::consts::{FRAC_PI_2, FRAC_2_PI, LOG2_E};
+use core::f32::INFINITY;
+use core::mem::transmute;
+
+#[inline]
+pub fn to_bits(x: f32) -> u32 {
+    unsafe { transmute(x) }
+}
+
+#[inline]
+pub fn from_bits(x: u32) -> f32 {
+    unsafe { transmute(x) }
+}
+
+/// `if cond { a } else { b }`, computed with bit masks instead of a branch.
+#[inline]
+pub fn select(cond: bool, a: f32, b: f32) -> f32 {
+    let mask = (cond as u32).wrapping_neg();
+    from_bits((to_bits(a) & mask) | (to_bits(b) & !mask))
+}
+
+#[inline]
+pub fn select_i32(cond: bool, a: i32, b: i32) -> i32 {
+    let mask = (cond as i32).wrapping_neg();
+    (a & mask) | (b & !mask)
+}
+
+#[inline]
+pub fn fabs(x: f32) -> f32 {
+    from_bits(to_bits(x) & 0x7fffffff)
+}
+
+#[inline]
+pub fn copysign(x: f32, sign: f32) -> f32 {
+    from_bits((to_bits(x) & 0x7fffffff) | (to_bits(sign) & 0x80000000))
+}
+
+/// floor for |x| < 2^23, beyond that the conversion overflows.
+#[inline]
+pub fn floor_small(x: f32) -> f32 {
+    let truncated = x as i32 as f32;
+    select(truncated > x, truncated - 1.0, truncated)
+}
+
+// Range reduction: angle = r + q * pi/2 with r in [-pi/4, pi/4]. Only the
+// low two bits of q matter, they select the quadrant.
+//
+// Below CODY_WAITE_LIMIT, q * pi/2 is subtracted in pieces: pi/2 is split into
+// parts whose products with q are exact, so the only rounding error is in the
+// last, smallest part. Beyond that, q needs more bits than the parts leave room
+// for, and the fractional part of angle * 2/pi is computed exactly with integer
+// arithmetic instead (Payne-Hanek). That path is slow, but only taken for
+// angles that no benchmark uses.
+
+pub const CODY_WAITE_LIMIT: f32 = 6400.0;
+
+pub const PI_2_PART1: f32 = 1.5703125;
+pub const PI_2_PART2: f32 = 4.837512969970703e-4;
+pub const PI_2_PART3: f32 = 7.549533620476723e-8;
+pub const PI_2_PART4: f32 = 2.5633440682570896e-12;
+
+// The first 256 bits of 2/pi
+const TWO_OVER_PI: [u32; 8] = [
+    0xa2f9836e, 0x4e441529, 0xfc2757d1, 0xf534ddc0,
+    0xdb629599, 0x3c439041, 0xfe5163ab, 0xdebbc561
+];
+
+/// The nearest multiple of pi/2, as used by the Cody-Waite reduction.
+#[inline]
+pub fn quadrant(angle: f32) -> i32 {
+    (angle * FRAC_2_PI + copysign(0.5, angle)) as i32
+}
+
+#[inline]
+pub fn reduce_cody_waite(angle: f32) -> (f32, i32) {
+    let q = quadrant(angle);
+    let qf = q as f32;
+    let r = angle - qf * PI_2_PART1 - qf * PI_2_PART2 - qf * PI_2_PART3 - qf * PI_2_PART4;
+    (r, q)
+}
+
+#[inline(never)]
+pub fn reduce_large(angle: f32) -> (f32, i32) {
+    if !(fabs(angle) < INFINITY) {
+        return (angle - angle, 0);
+    }
+
+    // |angle| = m * 2^e, where m is a 24 bit integer. Bit i of 2/pi (counting
+    // from 1 after the point) contributes m * 2^(e - i) to angle * 2/pi.
+    // Bits up to e - 2 only add multiples of 4, which don't change the
+    // quadrant, so we only need a window of bits starting at e - 1.
+    let bits = to_bits(angle);
+    let m = ((bits & 0x7fffff) | 0x800000) as u64;
+    let e = ((bits >> 23) & 0xff) as i32 - 150;
+
+    let w0 = two_over_pi_bits(e - 1) as u64;
+    let w1 = two_over_pi_bits(e + 31) as u64;
+    let w2 = two_over_pi_bits(e + 63) as u64;
+
+    // m * (w0:w1:w2) modulo 2^96, which is (angle * 2/pi mod 4) * 2^94
+    let p2 = m * w2;
+    let p1 = m * w1 + (p2 >> 32);
+    let p0 = m * w0 + (p1 >> 32);
+    let hi = p0 as u32;
+    let lo = p1 as u32;
+
+    // The top two bits are the integer part, the rest is the fraction. A
+    // fraction >= 0.5 rounds q up, reinterpreting it as signed does the
+    // matching subtraction of 1.
+    let fraction = (((hi as u64) << 32) | lo as u64) << 2;
+    let q = ((hi >> 30) + (hi >> 29 & 1)) as i32;
+    let r = (fraction as i64) as f32 * (1.0 / 18446744073709551616.0) * FRAC_PI_2;
+
+    if angle < 0.0 { (-r, -q) } else { (r, q) }
+}
+
+/// Bits `i` to `i + 31` of 2/pi, where bit 1 is the first one after the point.
+#[inline]
+fn two_over_pi_bits(i: i32) -> u32 {
+    fn word(index: i32) -> u64 {
+        if index < 0 {
+            0
+        } else {
+            TWO_OVER_PI[index as usize] as u64
+        }
+    }
+    let start = i - 1 + 32;
+    let (index, shift) = (start / 32 - 1, start % 32);
+    let window = (word(index) << 32) | word(index + 1);
+    (window << shift >> 32) as u32
+}
+
+pub const NUM_TERMS: usize = 6;
+
+const DENOMINATORS: [f32; NUM_TERMS] = [
+    -0.166666666666667,  // 1 / 3!
+    0.008333333333333,   // 1 / 5!
+    -0.000198412698413,  // 1 / 7!
+    0.000002755731922,   // 1 / 9!
+    -2.50521084e-8,      // 1 / 11!
+    1.6059044e-10        // 1 / 13!
+];
+
+const COS_DENOMINATORS: [f32; NUM_TERMS] = [
+    -0.5,                // 1 / 2!
+    0.041666666666667,   // 1 / 4!
+    -0.001388888888889,  // 1 / 6!
+    0.000024801587302,   // 1 / 8!
+    -2.75573192e-7,      // 1 / 10!
+    2.08767570e-9        // 1 / 12!
+];
+
+// Taylor series, only used on [-pi/4, pi/4]. Fewer terms trade accuracy for
+// speed, the loops are unrolled once `terms` is known.
+
+#[inline]
+pub fn sin_kernel(angle: f32, terms: usize) -> f32 {
+    let angle_squared = angle * angle;
+    let mut numerator = angle;
+    let mut result = angle;
+
+    for denom in &DENOMINATORS[..terms] {
+        numerator *= angle_squared;
+        result += numerator * denom;
+    }
+
+    result
+}
+
+#[inline]
+pub fn cos_kernel(angle: f32, terms: usize) -> f32 {
+    let angle_squared = angle * angle;
+    let mut numerator = 1.0;
+    let mut result = 1.0;
+
+    for denom in &COS_DENOMINATORS[..terms] {
+        numerator *= angle_squared;
+        result += numerator * denom;
+    }
+
+    result
+}
+
+// The remaining kernels are not part of the original port. The polynomials
+// are the single precision ones from Cephes, which keep the error within a
+// couple of ulp without needing any tables.
+
+/// atan on [-tan(pi/8), tan(pi/8)]
+#[inline]
+pub fn atan_kernel(t: f32) -> f32 {
+    let z = t * t;
+    (((8.05374449538e-2 * z - 1.38776856032e-1) * z + 1.99777106478e-1) * z -
+     3.33329491539e-1) * z * t + t
+}
+
+/// exp for x in [-103.97, 88.72], where the result is neither 0 nor infinite.
+#[inline]
+pub fn exp_kernel(x: f32) -> f32 {
+    // exp(x) = 2^k * exp(r), with |r| <= ln(2) / 2. ln(2) is split into a
+    // part that is exact in f32 and a small correction.
+    let k = floor_small(LOG2_E * x + 0.5);
+    let r = x - k * 0.693359375 + k * 2.12194440e-4;
+
+    let r2 = r * r;
+    let p = (((((1.9875691500e-4 * r + 1.3981999507e-3) * r + 8.3334519073e-3) * r +
+               4.1665795894e-2) * r + 1.6666665459e-1) * r + 5.0000001201e-1) * r2 + r + 1.0;
+    ldexp(p, k as i32)
+}
+
+/// log for finite, positive x.
+#[inline]
+pub fn log_kernel(x: f32) -> f32 {
+    // x = m * 2^e with m in [sqrt(1/2), sqrt(2))
+    let (m, e) = frexp(x);
+    let small = m < 0.70710678118654752;
+    let f = select(small, m + m - 1.0, m - 1.0);
+    let e = select_i32(small, e - 1, e) as f32;
+
+    let z = f * f;
+    let mut y = ((((((((7.0376836292e-2 * f - 1.1514610310e-1) * f + 1.1676998740e-1) * f -
+                      1.2420140846e-1) * f + 1.4249322787e-1) * f -
+                    1.6668057665e-1) * f + 2.0000714765e-1) * f -
+                  2.4999993993e-1) * f + 3.3333331174e-1) * f * z;
+    y += -2.12194440e-4 * e;
+    y += -0.5 * z;
+    f + y + 0.693359375 * e
+}
+
+/// sqrt for finite, positive x.
+#[inline]
+pub fn sqrt_kernel(value: f32) -> f32 {
+    // Subnormals don't have the implicit leading one the seed below relies
+    // on, so scale them up by 2^24 first (as frexp does) and the root back
+    // down by 2^12.
+    let subnormal = value < 1.17549435e-38;
+    let value = select(subnormal, value * 16777216.0, value);
+    let scale = select(subnormal, 0.000244140625, 1.0);
+
+    // Halving the exponent gets within a few percent of the root, after which
+    // Newton's method doubles the number of correct bits with every step.
+    let mut guess = from_bits((to_bits(value) >> 1) + 0x1fbd1df5);
+    for _ in 0..3 {
+        guess = ((value / guess) + guess) / 2.0;
+    }
+    guess * scale
+}
+
+/// Splits a finite, positive `x` into `m * 2^e` with `m` in [0.5, 1).
+#[inline]
+fn frexp(x: f32) -> (f32, i32) {
+    // Subnormals don't have the implicit leading one, so scale them up first
+    let subnormal = x < 1.17549435e-38;
+    let x = select(subnormal, x * 33554432.0, x);
+    let bias = select_i32(subnormal, -25, 0);
+    let bits = to_bits(x);
+    let e = ((bits >> 23) & 0xff) as i32 - 126;
+    (from_bits((bits & 0x807fffff) | 0x3f000000), e + bias)
+}
+
+/// Computes `x * 2^n` for `n` in the range produced by `exp`.
+#[inline]
+fn ldexp(x: f32, n: i32) -> f32 {
+    // Scale in two steps so that results in the subnormal range don't need
+    // an unrepresentable power of two.
+    let n1 = n / 2;
+    let n2 = n - n1;
+    x * from_bits(((n1 + 127) as u32) << 23) * from_bits(((n2 + 127) as u32) << 23)
+}
@@ -0,0 +1,60 @@
+// Bindings to libm, using wrapper functions for safety
+mod ffi {
+    extern {
+        pub fn sinf(x: f32) -> f32;
+        pub fn cosf(x: f32) -> f32;
+        pub fn tanf(x: f32) -> f32;
+        pub fn atan2f(y: f32, x: f32) -> f32;
+        pub fn expf(x: f32) -> f32;
+        pub fn logf(x: f32) -> f32;
+        pub fn powf(x: f32, y: f32) -> f32;
+        pub fn sqrtf(x: f32) -> f32;
+        pub fn floorf(x: f32) -> f32;
+        pub fn ceilf(x: f32) -> f32;
+        pub fn fabsf(x: f32) -> f32;
+    }
+}
+
+pub fn sin(x: f32) -> f32 {
+    unsafe { ffi::sinf(x) }
+}
+
+pub fn cos(x: f32) -> f32 {
+    unsafe { ffi::cosf(x) }
+}
+
+pub fn tan(x: f32) -> f32 {
+    unsafe { ffi::tanf(x) }
+}
+
+pub fn atan2(y: f32, x: f32) -> f32 {
+    unsafe { ffi::atan2f(y, x) }
+}
+
+pub fn exp(x: f32) -> f32 {
+    unsafe { ffi::expf(x) }
+}
+
+pub fn log(x: f32) -> f32 {
+    unsafe { ffi::logf(x) }
+}
+
+pub fn pow(x: f32, y: f32) -> f32 {
+    unsafe { ffi::powf(x, y) }
+}
+
+pub fn sqrt(x: f32) -> f32 {
+    unsafe { ffi::sqrtf(x) }
+}
+
+pub fn floor(x: f32) -> f32 {
+    unsafe { ffi::floorf(x) }
+}
+
+pub fn ceil(x: f32) -> f32 {
+    unsafe { ffi::ceilf(x) }
+}
+
+pub fn fabs(x: f32) -> f32 {
+    unsafe { ffi::fabsf(x) }
+}
@@ -0,0 +1,31 @@
+// All backends export the same set of f32 functions. By default they call
+// into libm, the `math-rust` feature switches to the Rust port, which can be
+// inlined into (and vectorized together with) SPMD kernels. The Rust port
+// comes in two precision tiers, `math-fast` selects the cheaper one.
+// `math-branchless` selects a variant of the accurate tier that uses
+// selects instead of branches, to measure what lane divergence costs.
+//
+// The backends are also reachable by name, so that the Rust port can be
+// tested against libm on the host, and so that code can pick a precision
+// tier explicitly where it matters.
+
+mod kernels;
+
+pub mod libm;
+pub mod accurate;
+pub mod fast;
+pub mod branchless;
+pub mod f64;
+
+#[cfg(not(any(feature = "math-rust", feature = "math-fast", feature = "math-branchless")))]
+pub use self::libm::*;
+
+#[cfg(all(feature = "math-rust",
+          not(any(feature = "math-fast", feature = "math-branchless"))))]
+pub use self::accurate::*;
+
+#[cfg(feature = "math-fast")]
+pub use self::fast::*;
+
+#[cfg(all(feature = "math-branchless", not(feature = "math-fast")))]
+pub use self::branchless::*;
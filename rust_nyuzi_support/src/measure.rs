@@ -0,0 +1,67 @@
+// Repeated measurement of a benchmark. A single run mixes cold-cache effects
+// into the cycle count, so `measure` first runs the benchmark a few times
+// untimed, then times a number of runs and summarizes them. Every run is
+// preceded by `setup`, outside the timed region, so each one starts from the
+// same inputs.
+//
+// Regions (see `timer`) inside the benchmark add up over all the runs.
+use core::cmp;
+use timer::cycles;
+
+/// The most timed runs `measure` keeps, more are ignored.
+pub const MAX_RUNS: usize = 64;
+
+/// Cycle counts of the timed runs.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: u32,
+    pub median: u32,
+    pub max: u32,
+}
+
+/// Runs `setup` and then `run`, `warmup` times untimed and then `runs` times
+/// timed. None if `setup` fails or there are no timed runs.
+pub fn measure<S, R>(mut setup: S, mut run: R, warmup: usize, runs: usize) -> Option<Stats>
+    where S: FnMut() -> bool,
+          R: FnMut()
+{
+    let runs = cmp::min(runs, MAX_RUNS);
+    if runs == 0 {
+        return None;
+    }
+
+    let mut samples = [0u32; MAX_RUNS];
+    for i in 0..warmup + runs {
+        if !setup() {
+            return None;
+        }
+        let start = cycles();
+        run();
+        let elapsed = cycles().wrapping_sub(start);
+        if i >= warmup {
+            samples[i - warmup] = elapsed;
+        }
+    }
+
+    let samples = &mut samples[..runs];
+    sort(samples);
+    Some(Stats {
+        runs: runs,
+        min: samples[0],
+        // The upper one of the two middle samples if there is an even number
+        median: samples[runs / 2],
+        max: samples[runs - 1],
+    })
+}
+
+// Insertion sort, there are at most MAX_RUNS samples
+fn sort(x: &mut [u32]) {
+    for i in 1..x.len() {
+        let mut j = i;
+        while j > 0 && x[j - 1] > x[j] {
+            x.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Nyuzi's two hardware performance counters. Each counter is assigned an
+// event through a select register and then counts it, as a 64 bit value split
+// across two control registers.
+//
+// Events are identified by the raw numbers from NyuziProcessor's
+// hardware/core/defines.svh (instructions issued, cache misses, ...). The
+// numbering has changed between hardware revisions, so this module passes
+// them through rather than naming them.
+//
+// Like the cycle counter, the counters are per core and keep running, so only
+// differences between two reads are meaningful. They count for all threads.
+// Once they are configured, every `timer::Region` also records the events
+// counted inside it.
+use core::ops::Add;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A raw event number, see the module comment.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Event(pub u32);
+
+pub const NUM_COUNTERS: usize = 2;
+
+/// Values of both counters at one point in time.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Counts(pub [u64; NUM_COUNTERS]);
+
+impl Counts {
+    /// Events counted since `earlier` was read.
+    pub fn since(self, earlier: Counts) -> Counts {
+        Counts([self.0[0].wrapping_sub(earlier.0[0]), self.0[1].wrapping_sub(earlier.0[1])])
+    }
+}
+
+impl Add for Counts {
+    type Output = Counts;
+
+    fn add(self, other: Counts) -> Counts {
+        Counts([self.0[0].wrapping_add(other.0[0]), self.0[1].wrapping_add(other.0[1])])
+    }
+}
+
+static CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+// Control register numbers can only be immediates, hence one function per
+// register. The numbers are CR_PERF_EVENT_SELECT0 and following.
+#[cfg(target_arch="nyuzi")]
+mod cr {
+    macro_rules! get_control_register {
+        ($read: ident, $asm: expr) => {
+            #[inline]
+            pub fn $read() -> u32 {
+                let value: u32;
+                unsafe { asm!($asm : "=r"(value) : : : "volatile") }
+                value
+            }
+        }
+    }
+
+    macro_rules! set_control_register {
+        ($write: ident, $asm: expr) => {
+            #[inline]
+            pub fn $write(value: u32) {
+                unsafe { asm!($asm : : "r"(value) : : "volatile") }
+            }
+        }
+    }
+
+    get_control_register!(event_select0, "getcr $0, 22");
+    get_control_register!(event_select1, "getcr $0, 23");
+    set_control_register!(set_event_select0, "setcr $0, 22");
+    set_control_register!(set_event_select1, "setcr $0, 23");
+    get_control_register!(count0_lo, "getcr $0, 24");
+    get_control_register!(count0_hi, "getcr $0, 25");
+    get_control_register!(count1_lo, "getcr $0, 26");
+    get_control_register!(count1_hi, "getcr $0, 27");
+}
+
+/// Assigns an event to each counter. Takes effect immediately, the counts
+/// are not reset.
+pub fn configure(event0: Event, event1: Event) {
+    select(event0, event1);
+    CONFIGURED.store(true, Ordering::Relaxed);
+}
+
+/// Whether `configure` has been called, i.e. whether counting means anything.
+pub fn configured() -> bool {
+    CONFIGURED.load(Ordering::Relaxed)
+}
+
+/// `configure` for harness.cpp, see PERF_EVENT0 and PERF_EVENT1 there.
+#[no_mangle]
+pub extern "C" fn nyuzi_perf_configure(event0: u32, event1: u32) {
+    configure(Event(event0), Event(event1));
+}
+
+#[cfg(target_arch="nyuzi")]
+fn select(event0: Event, event1: Event) {
+    cr::set_event_select0(event0.0);
+    cr::set_event_select1(event1.0);
+}
+
+/// The events the counters are currently assigned to.
+#[cfg(target_arch="nyuzi")]
+pub fn events() -> (Event, Event) {
+    (Event(cr::event_select0()), Event(cr::event_select1()))
+}
+
+#[cfg(target_arch="nyuzi")]
+#[inline]
+fn read_split(lo: fn() -> u32, hi: fn() -> u32) -> u64 {
+    // The low half may carry into the high half between the two reads
+    loop {
+        let h = hi();
+        let l = lo();
+        if hi() == h {
+            return ((h as u64) << 32) | l as u64;
+        }
+    }
+}
+
+#[cfg(target_arch="nyuzi")]
+pub fn read() -> Counts {
+    Counts([read_split(cr::count0_lo, cr::count0_hi), read_split(cr::count1_lo, cr::count1_hi)])
+}
+
+// Keeps host builds working, there are no counters to read
+#[cfg(not(target_arch="nyuzi"))]
+fn select(_: Event, _: Event) {}
+
+#[cfg(not(target_arch="nyuzi"))]
+pub fn events() -> (Event, Event) {
+    (Event(0), Event(0))
+}
+
+#[cfg(not(target_arch="nyuzi"))]
+pub fn read() -> Counts {
+    Counts::default()
+}
+
+/// Counts the configured events from the time it is started.
+pub struct Measurement {
+    start: Counts,
+}
+
+impl Measurement {
+    pub fn start() -> Self {
+        Measurement { start: read() }
+    }
+
+    /// Events counted so far.
+    pub fn elapsed(&self) -> Counts {
+        read().since(self.start)
+    }
+}
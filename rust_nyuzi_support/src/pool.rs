@@ -0,0 +1,59 @@
+// Buffers sized at runtime, for benchmarks whose problem size is a parameter.
+// There's no heap on bare metal, so this is a bump allocator over a static
+// array. Buffers are not freed individually: `reset` frees all of them at
+// once, which the functions generated by `bench!` do before setting up a
+// benchmark.
+use core::mem::{align_of, size_of};
+use core::slice;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Capacity of the pool in bytes.
+pub const POOL_BYTES: usize = 2 << 20;
+
+// u64s, so that buffers of any element type the benchmarks use are aligned
+static mut POOL: [u64; POOL_BYTES / 8] = [0; POOL_BYTES / 8];
+
+// Allocation is atomic because host tests run in parallel
+static USED: AtomicUsize = AtomicUsize::new(0);
+
+/// A buffer of `len` copies of `value`, or None if the pool is exhausted.
+pub fn alloc<T: Copy>(len: usize, value: T) -> Option<&'static mut [T]> {
+    let align = align_of::<T>();
+    assert!(align <= align_of::<u64>());
+    let bytes = match len.checked_mul(size_of::<T>()) {
+        Some(bytes) => bytes,
+        None => return None,
+    };
+
+    let mut used = USED.load(Ordering::Relaxed);
+    let start = loop {
+        let start = (used + align - 1) & !(align - 1);
+        if bytes > POOL_BYTES - start {
+            return None;
+        }
+        match USED.compare_exchange(used, start + bytes, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => break start,
+            Err(previous) => used = previous,
+        }
+    };
+
+    unsafe {
+        let ptr = (POOL.as_mut_ptr() as *mut u8).offset(start as isize) as *mut T;
+        let buffer = slice::from_raw_parts_mut(ptr, len);
+        for x in buffer.iter_mut() {
+            *x = value;
+        }
+        Some(buffer)
+    }
+}
+
+/// Bytes allocated since the last reset, including padding.
+pub fn used() -> usize {
+    USED.load(Ordering::Relaxed)
+}
+
+/// Frees all buffers. Unsafe because buffers allocated before must not be
+/// used afterwards.
+pub unsafe fn reset() {
+    USED.store(0, Ordering::Relaxed);
+}
@@ -0,0 +1,186 @@
+// Benchmark registry entries, as generated by `bench!`. With `--cfg registry`
+// every benchmark crate exports a `BENCHMARKS` table of these, which
+// rust_nyuzi_staticlib combines so the harness can pick a benchmark at
+// runtime.
+use benchmark::Size;
+use measure::{self, Stats};
+
+pub struct Entry {
+    // Both NUL terminated, so they can be handed to C
+    pub name: &'static str,
+    pub variant: &'static str,
+    pub setup: extern "C" fn(usize, usize) -> bool,
+    pub func: extern "C" fn(),
+    pub verify: extern "C" fn() -> bool,
+    pub equivalent: extern "C" fn(usize, usize, u32) -> bool,
+}
+
+impl Entry {
+    pub fn name(&self) -> &'static str {
+        &self.name[..self.name.len() - 1]
+    }
+
+    pub fn variant(&self) -> &'static str {
+        &self.variant[..self.variant.len() - 1]
+    }
+
+    /// See `Benchmark::setup`.
+    pub fn setup(&self, size: Size) -> bool {
+        (self.setup)(size.n, size.steps)
+    }
+
+    pub fn run(&self) {
+        (self.func)()
+    }
+
+    pub fn verify(&self) -> bool {
+        (self.verify)()
+    }
+
+    /// Whether the SPMD (and intrin, if any) variants give the same outputs
+    /// as the scalar one, see `benchmark::equivalent`.
+    pub fn equivalent(&self, size: Size, max_ulps: u32) -> bool {
+        (self.equivalent)(size.n, size.steps, max_ulps)
+    }
+
+    /// Sets up and runs the benchmark repeatedly, see `measure::measure`.
+    /// Afterwards the outputs are those of the last run, which the caller
+    /// can check with `verify` (the harness does).
+    pub fn measure(&self, size: Size, warmup: usize, runs: usize) -> Option<Stats> {
+        measure::measure(|| self.setup(size), || self.run(), warmup, runs)
+    }
+}
+
+/// Exports benchmarks implementing `Benchmark`, and the registry table of the
+/// crate. Each benchmark is given as
+///
+/// ```ignore
+/// "name" => static STATE: Type = init;
+/// ```
+///
+/// with `, intrin` before the semicolon for benchmarks that implement
+/// `IntrinBenchmark`. `STATE` holds the benchmark, and the functions
+/// generated for it are exported under the names harness.cpp links against:
+/// `name_setup`, `name_scalar`, `name_spmd`, `name_intrin`, `name_verify`
+/// and `name_equivalent`. All but `name_equivalent` call the stages of the
+/// benchmark, `name_setup(n, steps)` takes the fields of a `Size` and first
+/// resets the pool. `name_equivalent(n, steps, max_ulps)` runs every variant
+/// and compares it to the scalar one, using a second copy of the benchmark
+/// for the scalar outputs. The functions are compiled in either with
+/// `--cfg registry` or when the `benchmark` (and, for the variants, `variant`)
+/// cfgs match. On the Rust side they live in a module named after `STATE`.
+#[macro_export]
+macro_rules! bench {
+    // The benchmarks are handled one at a time, collecting the variants of
+    // the table as (name, STATE, variant)
+    (@munch [$($variants: tt)*]
+     $name: tt => static $state: ident: $Type: ty = $init: expr, intrin; $($rest: tt)*) => {
+        bench! {
+            @bench $name => static $state: $Type = $init,
+            runs: [$crate::benchmark::Benchmark::run_spmd,
+                   $crate::benchmark::IntrinBenchmark::run_intrin],
+            {
+                #[export_name = concat!($name, "_intrin")]
+                #[cfg(any(registry, all(benchmark = $name, variant = "intrin")))]
+                pub extern "C" fn intrin() {
+                    unsafe { $crate::benchmark::IntrinBenchmark::run_intrin(&mut super::$state) }
+                }
+            }
+        }
+        bench! {
+            @munch [$($variants)* ($name, $state, scalar) ($name, $state, spmd)
+                    ($name, $state, intrin)]
+            $($rest)*
+        }
+    };
+    (@munch [$($variants: tt)*]
+     $name: tt => static $state: ident: $Type: ty = $init: expr; $($rest: tt)*) => {
+        bench! {
+            @bench $name => static $state: $Type = $init,
+            runs: [$crate::benchmark::Benchmark::run_spmd],
+            {}
+        }
+        bench! {
+            @munch [$($variants)* ($name, $state, scalar) ($name, $state, spmd)]
+            $($rest)*
+        }
+    };
+    (@munch [$(($name: tt, $state: ident, $variant: ident))*]) => {
+        #[cfg(registry)]
+        pub const BENCHMARKS: &'static [$crate::registry::Entry] = &[$(
+            $crate::registry::Entry {
+                name: concat!($name, "\0"),
+                variant: concat!(stringify!($variant), "\0"),
+                setup: $state::setup,
+                func: $state::$variant,
+                verify: $state::verify,
+                equivalent: $state::equivalent,
+            }
+        ),*];
+    };
+    (@bench $name: tt => static $state: ident: $Type: ty = $init: expr,
+     runs: [$($run: expr),*],
+     { $($intrin: tt)* }) => {
+        static mut $state: $Type = $init;
+
+        #[allow(non_snake_case)]
+        pub mod $state {
+            // For `$Type`, unused if the cfgs select no function here
+            #[allow(unused_imports)]
+            use super::*;
+
+            #[export_name = concat!($name, "_setup")]
+            #[cfg(any(registry, benchmark = $name))]
+            pub extern "C" fn setup(n: usize, steps: usize) -> bool {
+                let size = $crate::benchmark::Size { n: n, steps: steps };
+                unsafe {
+                    $crate::pool::reset();
+                    $crate::benchmark::Benchmark::setup(&mut super::$state, size)
+                }
+            }
+
+            #[export_name = concat!($name, "_scalar")]
+            #[cfg(any(registry, all(benchmark = $name, variant = "scalar")))]
+            pub extern "C" fn scalar() {
+                unsafe { $crate::benchmark::Benchmark::run_scalar(&mut super::$state) }
+            }
+
+            #[export_name = concat!($name, "_spmd")]
+            #[cfg(any(registry, all(benchmark = $name, variant = "spmd")))]
+            pub extern "C" fn spmd() {
+                unsafe { $crate::benchmark::Benchmark::run_spmd(&mut super::$state) }
+            }
+
+            $($intrin)*
+
+            #[export_name = concat!($name, "_verify")]
+            #[cfg(any(registry, benchmark = $name))]
+            pub extern "C" fn verify() -> bool {
+                unsafe { $crate::benchmark::Benchmark::verify(&super::$state) }
+            }
+
+            #[export_name = concat!($name, "_equivalent")]
+            #[cfg(any(registry, benchmark = $name))]
+            pub extern "C" fn equivalent(n: usize, steps: usize, max_ulps: u32) -> bool {
+                static mut SCALAR: $Type = $init;
+                let size = $crate::benchmark::Size { n: n, steps: steps };
+                let mut equivalent = true;
+                $(
+                    unsafe {
+                        $crate::pool::reset();
+                        equivalent = equivalent &&
+                                     $crate::benchmark::equivalent(&mut SCALAR,
+                                                                   &mut super::$state,
+                                                                   $run,
+                                                                   size,
+                                                                   max_ulps);
+                    }
+                )*
+                equivalent
+            }
+        }
+    };
+    ($($benches: tt)*) => {
+        bench!(@munch [] $($benches)*);
+    };
+}
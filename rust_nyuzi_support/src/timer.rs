@@ -0,0 +1,156 @@
+// Cycle counting for timing phases of a benchmark from Rust. harness.cpp
+// times the benchmark function as a whole; a `Region` times part of it and
+// records the result in a table, which harness.cpp prints after the total
+// (printing right away would be counted in the enclosing regions). When the
+// performance counters are configured, regions also record the events counted
+// inside them (see `perf`).
+//
+// The table is not synchronized, so regions must not be created inside
+// SPMD kernels.
+use core::fmt::Write;
+use console::Console;
+use perf::{self, Counts};
+
+/// The cycle counter (control register 6). It wraps, so only differences
+/// are meaningful.
+#[cfg(target_arch="nyuzi")]
+#[inline]
+pub fn cycles() -> u32 {
+    let count: u32;
+    unsafe { asm!("getcr $0, 6" : "=r"(count) : : : "volatile") }
+    count
+}
+
+// Keeps host builds working, there's no comparable counter to read
+#[cfg(not(target_arch="nyuzi"))]
+#[inline]
+pub fn cycles() -> u32 {
+    0
+}
+
+const MAX_REGIONS: usize = 64;
+
+#[derive(Copy, Clone)]
+struct Record {
+    name: &'static str,
+    index: Option<usize>,
+    cycles: u32,
+    counts: Counts,
+}
+
+static mut RECORDS: [Record; MAX_REGIONS] = [Record {
+    name: "",
+    index: None,
+    cycles: 0,
+    counts: Counts([0; perf::NUM_COUNTERS]),
+}; MAX_REGIONS];
+static mut NUM_RECORDS: usize = 0;
+// Runs of regions that did not fit into the table any more
+static mut NUM_DROPPED: usize = 0;
+
+fn record(name: &'static str, index: Option<usize>, cycles: u32, counts: Counts) {
+    unsafe {
+        // Running the same region again (e.g. once per benchmark iteration)
+        // adds to its total.
+        for r in &mut RECORDS[..NUM_RECORDS] {
+            if r.name == name && r.index == index {
+                r.cycles = r.cycles.wrapping_add(cycles);
+                r.counts = r.counts + counts;
+                return;
+            }
+        }
+        if NUM_RECORDS < MAX_REGIONS {
+            RECORDS[NUM_RECORDS] = Record {
+                name: name,
+                index: index,
+                cycles: cycles,
+                counts: counts,
+            };
+            NUM_RECORDS += 1;
+        } else {
+            NUM_DROPPED += 1;
+        }
+    }
+}
+
+// Reading the counters takes time too, so they are left alone unless they
+// have been configured.
+fn counts() -> Counts {
+    if perf::configured() {
+        perf::read()
+    } else {
+        Counts::default()
+    }
+}
+
+/// Times the scope it lives in, from `new` until it is dropped.
+pub struct Region {
+    name: &'static str,
+    index: Option<usize>,
+    start: u32,
+    start_counts: Counts,
+}
+
+impl Region {
+    pub fn new(name: &'static str) -> Self {
+        Region::start(name, None)
+    }
+
+    /// One of a numbered series of regions, e.g. the iterations of a loop.
+    pub fn indexed(name: &'static str, index: usize) -> Self {
+        Region::start(name, Some(index))
+    }
+
+    fn start(name: &'static str, index: Option<usize>) -> Self {
+        let start_counts = counts();
+        Region { name: name, index: index, start: cycles(), start_counts: start_counts }
+    }
+
+    /// Cycles since the region started.
+    pub fn elapsed(&self) -> u32 {
+        cycles().wrapping_sub(self.start)
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed();
+        let counts = counts().since(self.start_counts);
+        record(self.name, self.index, elapsed, counts);
+    }
+}
+
+/// Prints one `region:<name>:<cycles>` line per recorded region (with the
+/// index as `<name>[<index>]`), followed by one
+/// `region_perf:<name>:<event>:<count>` line per counter if they are
+/// configured, then `region_overflow:<runs>` if runs of regions were dropped
+/// because there were more than MAX_REGIONS of them, and clears the table.
+/// Called by harness.cpp.
+#[no_mangle]
+pub extern "C" fn nyuzi_timer_report() {
+    let (event0, event1) = perf::events();
+    unsafe {
+        for r in &RECORDS[..NUM_RECORDS] {
+            let _ = match r.index {
+                Some(i) => writeln!(Console, "region:{}[{}]:{}", r.name, i, r.cycles),
+                None => writeln!(Console, "region:{}:{}", r.name, r.cycles),
+            };
+            if !perf::configured() {
+                continue;
+            }
+            for &(event, count) in &[(event0, r.counts.0[0]), (event1, r.counts.0[1])] {
+                let _ = match r.index {
+                    Some(i) => {
+                        writeln!(Console, "region_perf:{}[{}]:{}:{}", r.name, i, event.0, count)
+                    }
+                    None => writeln!(Console, "region_perf:{}:{}:{}", r.name, event.0, count),
+                };
+            }
+        }
+        if NUM_DROPPED > 0 {
+            let _ = writeln!(Console, "region_overflow:{}", NUM_DROPPED);
+        }
+        NUM_RECORDS = 0;
+        NUM_DROPPED = 0;
+    }
+}
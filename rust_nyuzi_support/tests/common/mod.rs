@@ -0,0 +1,165 @@
+// Error statistics and input sweeps shared by rust_trig_accuracy.rs and
+// rust_trig_accuracy_f64.rs, over either precision.
+use std::fmt::LowerExp;
+use std::ops::{Add, Sub, Mul, Div};
+
+pub const STEPS: usize = 200_000;
+
+/// What the sweeps need to know about f32 and f64.
+pub trait Float: Copy + PartialOrd + LowerExp +
+    Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+{
+    fn zero() -> Self;
+    fn from_usize(n: usize) -> Self;
+    fn is_nan(self) -> bool;
+    fn abs(self) -> Self;
+    fn ln(self) -> Self;
+    fn exp(self) -> Self;
+    /// The bits mapped from sign-magnitude onto a monotonic integer scale.
+    fn ordered(self) -> i64;
+}
+
+impl Float for f32 {
+    fn zero() -> Self { 0.0 }
+    fn from_usize(n: usize) -> Self { n as f32 }
+    fn is_nan(self) -> bool { self.is_nan() }
+    fn abs(self) -> Self { self.abs() }
+    fn ln(self) -> Self { self.ln() }
+    fn exp(self) -> Self { self.exp() }
+
+    fn ordered(self) -> i64 {
+        let bits = self.to_bits() as i32;
+        if bits < 0 {
+            (i32::min_value() - bits) as i64
+        } else {
+            bits as i64
+        }
+    }
+}
+
+impl Float for f64 {
+    fn zero() -> Self { 0.0 }
+    fn from_usize(n: usize) -> Self { n as f64 }
+    fn is_nan(self) -> bool { self.is_nan() }
+    fn abs(self) -> Self { self.abs() }
+    fn ln(self) -> Self { self.ln() }
+    fn exp(self) -> Self { self.exp() }
+
+    fn ordered(self) -> i64 {
+        let bits = self.to_bits() as i64;
+        if bits < 0 {
+            i64::min_value() - bits
+        } else {
+            bits
+        }
+    }
+}
+
+pub struct Stats<F> {
+    max_ulp: u64,
+    worst_input: F,
+    total_ulp: f64,
+    max_abs: F,
+    samples: usize,
+}
+
+impl<F: Float> Stats<F> {
+    pub fn new() -> Self {
+        Stats {
+            max_ulp: 0,
+            worst_input: F::zero(),
+            total_ulp: 0.0,
+            max_abs: F::zero(),
+            samples: 0,
+        }
+    }
+
+    pub fn record(&mut self, input: F, actual: F, expected: F) {
+        let ulp = ulp_error(actual, expected);
+        if ulp > self.max_ulp {
+            self.max_ulp = ulp;
+            self.worst_input = input;
+        }
+        self.total_ulp += ulp as f64;
+        if (actual - expected).abs() > self.max_abs {
+            self.max_abs = (actual - expected).abs();
+        }
+        self.samples += 1;
+    }
+
+    pub fn mean_ulp(&self) -> f64 {
+        self.total_ulp / self.samples as f64
+    }
+
+    pub fn report(&self, name: &str) {
+        println!("{:>8}: max {} ulp (at {:e}), mean {:.3} ulp, max abs {:e} over {} samples",
+                 name,
+                 self.max_ulp,
+                 self.worst_input,
+                 self.mean_ulp(),
+                 self.max_abs,
+                 self.samples);
+    }
+
+    pub fn check(&self, name: &str, max_ulp: u64, mean_ulp: f64) {
+        self.report(name);
+        assert!(self.max_ulp <= max_ulp,
+                "{}: max error {} ulp at {:e} exceeds {} ulp",
+                name,
+                self.max_ulp,
+                self.worst_input,
+                max_ulp);
+        assert!(self.mean_ulp() <= mean_ulp,
+                "{}: mean error {:.3} ulp exceeds {} ulp",
+                name,
+                self.mean_ulp(),
+                mean_ulp);
+    }
+}
+
+/// Distance between two floats in units in the last place. Two NaNs are
+/// considered equal, a NaN and a number are infinitely far apart.
+pub fn ulp_error<F: Float>(actual: F, expected: F) -> u64 {
+    if actual.is_nan() || expected.is_nan() {
+        return if actual.is_nan() && expected.is_nan() {
+            0
+        } else {
+            u64::max_value()
+        };
+    }
+    // The distance always fits into a u64, even where the difference of the
+    // i64s overflows
+    let (a, e) = (actual.ordered() as u64, expected.ordered() as u64);
+    if actual.ordered() >= expected.ordered() {
+        a.wrapping_sub(e)
+    } else {
+        e.wrapping_sub(a)
+    }
+}
+
+/// Evenly spaced samples in [lo, hi].
+pub fn linear<F: Float>(lo: F, hi: F) -> Vec<F> {
+    (0..STEPS + 1)
+        .map(|i| lo + (hi - lo) * (F::from_usize(i) / F::from_usize(STEPS)))
+        .collect()
+}
+
+/// Samples in [lo, hi] (both positive), evenly spaced on a log scale.
+pub fn geometric<F: Float>(lo: F, hi: F) -> Vec<F> {
+    let (lo, hi) = (lo.ln(), hi.ln());
+    (0..STEPS + 1)
+        .map(|i| (lo + (hi - lo) * (F::from_usize(i) / F::from_usize(STEPS))).exp())
+        .collect()
+}
+
+pub fn sweep<F, A, E>(inputs: &[F], actual: A, expected: E) -> Stats<F>
+    where F: Float,
+          A: Fn(F) -> F,
+          E: Fn(F) -> F
+{
+    let mut stats = Stats::new();
+    for &x in inputs {
+        stats.record(x, actual(x), expected(x));
+    }
+    stats
+}
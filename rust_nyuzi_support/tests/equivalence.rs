@@ -0,0 +1,39 @@
+// The ulp distances and comparisons used to check that SPMD variants give the
+// same outputs as the scalar ones.
+extern crate nyuzi_support;
+
+use nyuzi_support::benchmark::{Equivalent, ulps_f32, ulps_f64};
+use nyuzi_support::linalg::Vector3;
+use std::{f32, f64};
+
+#[test]
+fn ulps_f32_distance() {
+    assert_eq!(ulps_f32(1.0, 1.0), 0);
+    assert_eq!(ulps_f32(0.0, -0.0), 0);
+    assert_eq!(ulps_f32(1.0, 1.0 + f32::EPSILON), 1);
+    assert_eq!(ulps_f32(1.0 + f32::EPSILON, 1.0), 1);
+    // Across zero, through both denormals
+    assert_eq!(ulps_f32(-1e-45, 1e-45), 2);
+    assert_eq!(ulps_f32(f32::NAN, f32::NAN), 0);
+    assert_eq!(ulps_f32(f32::NAN, 1.0), u32::max_value());
+}
+
+#[test]
+fn ulps_f64_distance() {
+    assert_eq!(ulps_f64(1.0, 1.0 + f64::EPSILON), 1);
+    assert_eq!(ulps_f64(-1.0, 1.0), u32::max_value());
+    assert_eq!(ulps_f64(f64::MIN, f64::MAX), u32::max_value());
+    assert_eq!(ulps_f64(f64::NAN, f64::NAN), 0);
+}
+
+#[test]
+fn equivalent() {
+    assert!(3.equivalent(&3, 0));
+    assert!(!3.equivalent(&4, 100));
+    assert!(1.0f32.equivalent(&(1.0 + 2.0 * f32::EPSILON), 2));
+    assert!(!1.0f32.equivalent(&(1.0 + 2.0 * f32::EPSILON), 1));
+
+    let v = Vector3::new(1.0f64, 2.0, 3.0);
+    assert!(v.equivalent(&v, 0));
+    assert!(!v.equivalent(&Vector3::new(1.0, 2.0, 3.1), 4));
+}
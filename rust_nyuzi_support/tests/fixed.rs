@@ -0,0 +1,101 @@
+// Fixed point arithmetic and functions compared to the same computation in
+// f64. The tolerances are a few steps of the respective resolution.
+extern crate nyuzi_support;
+
+use nyuzi_support::fixed::{Q16_16, Q8_24};
+
+macro_rules! fixed_tests {
+    ($module: ident, $Fixed: ident, $range: expr) => {
+        mod $module {
+            use super::*;
+
+            const STEPS: i32 = 100_000;
+
+            fn resolution() -> f64 {
+                1.0 / (1u32 << $Fixed::FRAC_BITS) as f64
+            }
+
+            fn inputs(lo: f64, hi: f64) -> Vec<$Fixed> {
+                (0..STEPS + 1)
+                    .map(|i| $Fixed::from_f64(lo + (hi - lo) * (i as f64 / STEPS as f64)))
+                    .collect()
+            }
+
+            fn check<F, G>(name: &str, inputs: &[$Fixed], actual: F, expected: G, steps: f64)
+                where F: Fn($Fixed) -> $Fixed,
+                      G: Fn(f64) -> f64
+            {
+                let tolerance = steps * resolution();
+                for &x in inputs {
+                    let (a, e) = (actual(x).to_f64(), expected(x.to_f64()));
+                    assert!((a - e).abs() <= tolerance,
+                            "{}({:?}) = {}, expected {}",
+                            name,
+                            x,
+                            a,
+                            e);
+                }
+            }
+
+            #[test]
+            fn conversions() {
+                assert_eq!($Fixed::from_int(3).to_bits(), 3 << $Fixed::FRAC_BITS);
+                assert_eq!($Fixed::from_f32(-2.5).to_f32(), -2.5);
+                assert_eq!($Fixed::from_f64(-2.25).to_int(), -3);
+                assert_eq!($Fixed::ONE.to_f64(), 1.0);
+                assert!(($Fixed::PI.to_f64() - std::f64::consts::PI).abs() <= resolution());
+            }
+
+            #[test]
+            fn arithmetic() {
+                let (a, b) = ($Fixed::from_f64(1.75), $Fixed::from_f64(-0.5));
+                assert_eq!((a + b).to_f64(), 1.25);
+                assert_eq!((a - b).to_f64(), 2.25);
+                assert_eq!((a * b).to_f64(), -0.875);
+                assert_eq!((a / b).to_f64(), -3.5);
+                assert_eq!((a * 2).to_f64(), 3.5);
+                assert_eq!((a / 7).to_f64(), 0.25);
+                assert_eq!((-a).to_f64(), -1.75);
+                assert!(b < a);
+            }
+
+            #[test]
+            fn wrapping() {
+                let (max, min) = ($Fixed::from_bits(i32::max_value()),
+                                  $Fixed::from_bits(i32::min_value()));
+                let step = $Fixed::from_bits(1);
+                assert_eq!(max + step, min);
+                assert_eq!(min - step, max);
+                let mut x = max;
+                x += step;
+                assert_eq!(x, min);
+                x -= step;
+                assert_eq!(x, max);
+                assert_eq!(-min, min);
+                assert_eq!(min.abs(), min);
+                assert_eq!(max * 2, $Fixed::from_bits(-2));
+                assert_eq!(min / -1, min);
+                assert_eq!($Fixed::from_int(1 << (31 - $Fixed::FRAC_BITS)), min);
+            }
+
+            #[test]
+            fn sin() {
+                check("sin", &inputs(-$range, $range), |x| x.sin(), f64::sin, 4.0);
+            }
+
+            #[test]
+            fn cos() {
+                check("cos", &inputs(-$range, $range), |x| x.cos(), f64::cos, 4.0);
+            }
+
+            #[test]
+            fn sqrt() {
+                check("sqrt", &inputs(0.0, $range), |x| x.sqrt(), f64::sqrt, 1.0);
+                assert_eq!($Fixed::from_int(-4).sqrt(), $Fixed::ZERO);
+            }
+        }
+    }
+}
+
+fixed_tests!(q16_16, Q16_16, 1000.0);
+fixed_tests!(q8_24, Q8_24, 100.0);
@@ -0,0 +1,74 @@
+// The vector types through their host implementation, which loops over the
+// lanes.
+extern crate nyuzi_support;
+
+use nyuzi_support::intrin::{F32x16, I32x16, Mask, U32x16, LANES};
+
+fn ramp() -> I32x16 {
+    U32x16::lane_ids().as_i32()
+}
+
+#[test]
+fn arithmetic() {
+    let x = ramp();
+    assert_eq!((x + x).to_array(), (x * I32x16::splat(2)).to_array());
+    assert_eq!((x - x).to_array(), [0; LANES]);
+    assert_eq!((x / I32x16::splat(4)).extract(15), 3);
+    assert_eq!((x % I32x16::splat(4)).extract(15), 3);
+    assert_eq!((x << 2).extract(3), 12);
+    assert_eq!((I32x16::splat(-8) >> 1).extract(0), -4);
+    assert_eq!((U32x16::splat(!0) >> 28).extract(0), 15);
+    assert_eq!((I32x16::splat(i32::max_value()) + I32x16::splat(1)).extract(0),
+               i32::min_value());
+    assert_eq!((!I32x16::splat(0)).extract(0), -1);
+    assert_eq!(((x & I32x16::splat(1)) | I32x16::splat(2)).extract(5), 3);
+
+    let f = x.to_f32() * F32x16::splat(0.5);
+    assert_eq!(f.extract(3), 1.5);
+    assert_eq!(f.to_i32().extract(3), 1);
+    assert_eq!((-F32x16::splat(0.0)).extract(0).to_bits(), (-0.0f32).to_bits());
+    assert_eq!(f.map(|x| x * x).extract(4), 4.0);
+}
+
+#[test]
+fn comparisons_and_select() {
+    let x = ramp();
+    let small = x.lt(I32x16::splat(4));
+    let odd = (x & I32x16::splat(1)).eq(I32x16::splat(1));
+    let picked = (small | odd).select(x, I32x16::splat(-1)).to_array();
+    assert_eq!(&picked[..8], &[0, 1, 2, 3, -1, 5, -1, 7]);
+    assert_eq!((small & odd).select(F32x16::splat(1.0), F32x16::splat(0.0))
+                   .to_array()
+                   .iter()
+                   .sum::<f32>(),
+               2.0);
+
+    assert!(x.ge(I32x16::splat(0)).all());
+    assert!(x.gt(I32x16::splat(14)).any());
+    assert!(x.gt(I32x16::splat(15)).none());
+    assert_eq!(!x.le(I32x16::splat(15)), Mask::empty());
+    assert_eq!(U32x16::splat(!0).gt(U32x16::splat(0)), Mask::full());
+    assert!(I32x16::splat(-1).lt(I32x16::splat(0)).all());
+    assert!(F32x16::splat(::std::f32::NAN).ne(F32x16::splat(0.0)).all());
+}
+
+#[test]
+fn gather_scatter() {
+    let xs: Vec<f32> = (0..64).map(|i| i as f32).collect();
+    let evens = U32x16::lane_ids() << 1;
+    assert_eq!(F32x16::gather(&xs, evens).extract(5), 10.0);
+    assert_eq!(F32x16::load(&xs, 16).extract(0), 16.0);
+
+    let mut ys = vec![0; 32];
+    I32x16::splat(7).scatter(&mut ys, evens);
+    assert_eq!(&ys[..4], &[7, 0, 7, 0]);
+    ramp().store(&mut ys, 16);
+    assert_eq!(ys[31], 15);
+}
+
+#[test]
+#[should_panic]
+fn gather_out_of_bounds() {
+    let xs = [0u32; 15];
+    U32x16::load(&xs, 0);
+}
@@ -0,0 +1,117 @@
+// Vector and matrix operations in both precisions. The inputs are small
+// integers, so every result is exact and can be compared with `==`.
+extern crate nyuzi_support;
+
+use nyuzi_support::linalg::{Vector3, Vector4, Point3, Matrix3, Matrix4};
+
+macro_rules! linalg_tests {
+    ($module: ident, $S: ident) => {
+        mod $module {
+            use super::*;
+
+            fn vector3(x: $S, y: $S, z: $S) -> Vector3<$S> {
+                Vector3::new(x, y, z)
+            }
+
+            // Columns (1, 2, 3), (4, 5, 6), (7, 8, 10)
+            fn matrix3() -> Matrix3<$S> {
+                Matrix3::from_cols(vector3(1.0, 2.0, 3.0),
+                                   vector3(4.0, 5.0, 6.0),
+                                   vector3(7.0, 8.0, 10.0))
+            }
+
+            #[test]
+            fn dot() {
+                let (a, b) = (vector3(1.0, 2.0, 3.0), vector3(4.0, -5.0, 6.0));
+                assert_eq!(a.dot(b), 12.0);
+                assert_eq!(a.magnitude2(), 14.0);
+                assert_eq!(vector3(2.0, 3.0, 6.0).magnitude(), 7.0);
+                assert_eq!(Vector4::new(1.0, 2.0, 3.0, 4.0).dot(Vector4::new(1.0, 1.0, 1.0, -1.0)),
+                           2.0);
+            }
+
+            #[test]
+            fn cross() {
+                let (x, y, z) = (vector3(1.0, 0.0, 0.0),
+                                 vector3(0.0, 1.0, 0.0),
+                                 vector3(0.0, 0.0, 1.0));
+                assert_eq!(x.cross(y), z);
+                assert_eq!(y.cross(z), x);
+                assert_eq!(z.cross(x), y);
+                assert_eq!(y.cross(x), -z);
+
+                let (a, b) = (vector3(1.0, 2.0, 3.0), vector3(4.0, 5.0, 6.0));
+                assert_eq!(a.cross(b), vector3(-3.0, 6.0, -3.0));
+                assert_eq!(a.cross(b).dot(a), 0.0);
+                assert_eq!(a.cross(a), Vector3::zero());
+            }
+
+            #[test]
+            fn rows_and_columns() {
+                let m = matrix3();
+                assert_eq!(m.x, vector3(1.0, 2.0, 3.0));
+                assert_eq!(m.z, vector3(7.0, 8.0, 10.0));
+                assert_eq!(m.row(0), vector3(1.0, 4.0, 7.0));
+                assert_eq!(m.row(1), vector3(2.0, 5.0, 8.0));
+                assert_eq!(m.row(2), vector3(3.0, 6.0, 10.0));
+            }
+
+            #[test]
+            fn transpose() {
+                let m = matrix3();
+                let t = m.transpose();
+                assert_eq!(t.x, m.row(0));
+                assert_eq!(t.y, m.row(1));
+                assert_eq!(t.z, m.row(2));
+                assert_eq!(t.transpose(), m);
+                assert_eq!(Matrix4::<$S>::identity().transpose(), Matrix4::identity());
+            }
+
+            #[test]
+            fn matrix_vector() {
+                let m = matrix3();
+                assert_eq!(m * vector3(1.0, 0.0, 0.0), m.x);
+                // Each row dotted with the vector
+                assert_eq!(m * vector3(1.0, -1.0, 2.0), vector3(11.0, 13.0, 17.0));
+                assert_eq!(Matrix3::identity() * vector3(1.0, -1.0, 2.0), vector3(1.0, -1.0, 2.0));
+            }
+
+            #[test]
+            fn matrix_matrix() {
+                let m = matrix3();
+                assert_eq!(m * Matrix3::identity(), m);
+                assert_eq!(Matrix3::identity() * m, m);
+
+                let n = Matrix3::from_cols(vector3(0.0, 1.0, 0.0),
+                                           vector3(1.0, 0.0, 0.0),
+                                           vector3(0.0, 0.0, 2.0));
+                // Multiplying by n on the right swaps the first two columns
+                // and doubles the third one, on the left it does the same
+                // with the rows.
+                assert_eq!(m * n, Matrix3::from_cols(m.y, m.x, m.z * 2.0));
+                assert_eq!((n * m).transpose(),
+                           Matrix3::from_cols(m.row(1), m.row(0), m.row(2) * 2.0));
+                assert_eq!((m * n).transpose(), n.transpose() * m.transpose());
+            }
+
+            #[test]
+            fn transform_point() {
+                let p = Point3::new(1.0, 2.0, 3.0);
+                let translation = Matrix4::from_translation(vector3(10.0, -20.0, 0.5));
+                assert_eq!(translation.transform_point(p), Point3::new(11.0, -18.0, 3.5));
+                // Directions are not translated
+                assert_eq!(translation.transform_vector(p.to_vec()), p.to_vec());
+
+                // A projection onto the plane z = 1
+                let mut projection = Matrix4::<$S>::identity();
+                projection.w = Vector4::new(0.0, 0.0, 0.0, 0.0);
+                projection.z.w = 1.0;
+                assert_eq!(projection.transform_point(Point3::new(2.0, -4.0, 2.0)),
+                           Point3::new(1.0, -2.0, 1.0));
+            }
+        }
+    }
+}
+
+linalg_tests!(single, f32);
+linalg_tests!(double, f64);
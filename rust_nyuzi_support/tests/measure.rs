@@ -0,0 +1,37 @@
+// The run and setup bookkeeping of the measurement loop. There is no cycle
+// counter on the host, so the statistics themselves are all zero here.
+extern crate nyuzi_support;
+
+use nyuzi_support::measure::{measure, MAX_RUNS};
+use std::cell::Cell;
+
+#[test]
+fn runs_warmup_then_timed() {
+    let setups = Cell::new(0);
+    let mut runs = 0;
+    let setup = || {
+        setups.set(setups.get() + 1);
+        true
+    };
+    let stats = measure(setup, || runs += 1, 2, 5).unwrap();
+    assert_eq!((setups.get(), runs), (7, 7));
+    assert_eq!(stats.runs, 5);
+    assert!(stats.min <= stats.median && stats.median <= stats.max);
+}
+
+#[test]
+fn keeps_at_most_max_runs() {
+    let mut runs = 0;
+    let stats = measure(|| true, || runs += 1, 0, MAX_RUNS + 1).unwrap();
+    assert_eq!(runs, MAX_RUNS);
+    assert_eq!(stats.runs, MAX_RUNS);
+}
+
+#[test]
+fn fails_without_runs_or_setup() {
+    assert_eq!(measure(|| true, || (), 1, 0), None);
+
+    let mut runs = 0;
+    assert_eq!(measure(|| false, || runs += 1, 1, 1), None);
+    assert_eq!(runs, 0);
+}
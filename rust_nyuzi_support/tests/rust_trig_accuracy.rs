@@ -0,0 +1,177 @@
+// Accuracy of both tiers of the Rust math port (the `math-rust` and
+// `math-fast` features) compared to the platform libm, and a check that the
+// branchless variant (`math-branchless`) matches the accurate tier. Each
+// function is swept over its domain and the maximum and mean error in ulp is
+// checked against a per-function threshold. The thresholds describe
+// the current state of the port, so a test failure means accuracy regressed.
+//
+// Run with `cargo test -- --nocapture` to see the error statistics.
+extern crate nyuzi_support;
+
+mod common;
+
+use common::{linear, geometric, sweep};
+use nyuzi_support::math::{libm, accurate, fast, branchless};
+use std::f32::consts::PI;
+
+/// Samples over the whole finite range, evenly spaced on a log scale.
+fn full_range() -> Vec<f32> {
+    let positive = geometric(1e-30, 3e38);
+    let negative = positive.iter().map(|&x| -x);
+    positive.iter().cloned().chain(negative).collect()
+}
+
+#[test]
+fn sin() {
+    sweep(&linear(-2.0 * PI, 2.0 * PI), accurate::sin, libm::sin).check("sin", 3, 0.5);
+    sweep(&linear(-1e4, 1e4), accurate::sin, libm::sin).check("sin", 3, 0.5);
+    sweep(&full_range(), accurate::sin, libm::sin).check("sin", 3, 0.5);
+}
+
+#[test]
+fn cos() {
+    sweep(&linear(-2.0 * PI, 2.0 * PI), accurate::cos, libm::cos).check("cos", 3, 0.5);
+    sweep(&linear(-1e4, 1e4), accurate::cos, libm::cos).check("cos", 3, 0.5);
+    sweep(&full_range(), accurate::cos, libm::cos).check("cos", 3, 0.5);
+}
+
+#[test]
+fn tan() {
+    sweep(&linear(-1.5, 1.5), accurate::tan, libm::tan).check("tan", 6, 0.8);
+    sweep(&full_range(), accurate::tan, libm::tan).check("tan", 6, 0.8);
+}
+
+#[test]
+fn atan2() {
+    let inputs = linear(-100.0, 100.0);
+    for &x in &[-10.0, -1.0, -0.1, 0.1, 1.0, 10.0] {
+        let name = format!("atan2(y, {})", x);
+        sweep(&inputs, |y| accurate::atan2(y, x), |y| libm::atan2(y, x)).check(&name, 4, 1.0);
+        let name = format!("atan2({}, x)", x);
+        sweep(&inputs, |y| accurate::atan2(x, y), |y| libm::atan2(x, y)).check(&name, 4, 1.0);
+    }
+}
+
+#[test]
+fn exp() {
+    sweep(&linear(-103.0, 88.0), accurate::exp, libm::exp).check("exp", 2, 0.2);
+}
+
+#[test]
+fn log() {
+    sweep(&geometric(1e-40, 1e38), accurate::log, libm::log).check("log", 2, 0.2);
+    sweep(&linear(0.0, 4.0), accurate::log, libm::log).check("log", 2, 0.2);
+}
+
+#[test]
+fn pow() {
+    // pow is computed as exp(y * log(x)), so the error grows with the
+    // magnitude of y * log(x).
+    let bases = linear(0.01, 10.0);
+    for &y in &[-2.5, -1.0, 0.5, 2.0, 3.0] {
+        let name = format!("pow(x, {})", y);
+        sweep(&bases, |x| accurate::pow(x, y), |x| libm::pow(x, y)).check(&name, 64, 4.0);
+    }
+    let bases = linear(-10.0, 10.0);
+    for &y in &[-3.0, 2.0, 3.0, 4.0] {
+        let name = format!("pow(x, {})", y);
+        sweep(&bases, |x| accurate::pow(x, y), |x| libm::pow(x, y)).check(&name, 64, 4.0);
+    }
+}
+
+#[test]
+fn sqrt() {
+    sweep(&geometric(1e-45, 3e38), accurate::sqrt, libm::sqrt).check("sqrt", 1, 0.3);
+    sweep(&linear(0.0, 1e4), accurate::sqrt, libm::sqrt).check("sqrt", 1, 0.3);
+}
+
+#[test]
+fn floor() {
+    sweep(&linear(-1e4, 1e4), accurate::floor, libm::floor).check("floor", 0, 0.0);
+    sweep(&geometric(1.0, 1e38), accurate::floor, libm::floor).check("floor", 0, 0.0);
+}
+
+#[test]
+fn ceil() {
+    sweep(&linear(-1e4, 1e4), accurate::ceil, libm::ceil).check("ceil", 0, 0.0);
+    sweep(&geometric(1.0, 1e38), accurate::ceil, libm::ceil).check("ceil", 0, 0.0);
+}
+
+#[test]
+fn fabs() {
+    sweep(&linear(-1e4, 1e4), accurate::fabs, libm::fabs).check("fabs", 0, 0.0);
+}
+
+// The fast tier, checked over the ranges it is meant for
+
+#[test]
+fn fast_sin() {
+    sweep(&linear(-100.0, 100.0), fast::sin, libm::sin).check("fast sin", 1000, 6.0);
+}
+
+#[test]
+fn fast_cos() {
+    sweep(&linear(-100.0, 100.0), fast::cos, libm::cos).check("fast cos", 1000, 6.0);
+}
+
+#[test]
+fn fast_tan() {
+    sweep(&linear(-1.5, 1.5), fast::tan, libm::tan).check("fast tan", 128, 10.0);
+}
+
+#[test]
+fn fast_sqrt() {
+    sweep(&geometric(1e-30, 1e30), fast::sqrt, libm::sqrt).check("fast sqrt", 128, 32.0);
+}
+
+// The branchless variant of the accurate tier must give bit-identical results
+
+fn assert_identical<F, G>(name: &str, inputs: &[f32], actual: F, expected: G)
+    where F: Fn(f32) -> f32,
+          G: Fn(f32) -> f32
+{
+    for &x in inputs {
+        let (a, e) = (actual(x), expected(x));
+        assert!(a.to_bits() == e.to_bits() || (a.is_nan() && e.is_nan()),
+                "{}({:e}): branchless gives {:e}, accurate gives {:e}",
+                name,
+                x,
+                a,
+                e);
+    }
+}
+
+#[test]
+fn branchless_matches_accurate() {
+    let mut inputs = full_range();
+    inputs.extend(linear(-200.0, 200.0));
+    inputs.extend(&[0.0, -0.0, std::f32::INFINITY, std::f32::NEG_INFINITY, std::f32::NAN]);
+
+    assert_identical("sin", &inputs, branchless::sin, accurate::sin);
+    assert_identical("cos", &inputs, branchless::cos, accurate::cos);
+    assert_identical("tan", &inputs, branchless::tan, accurate::tan);
+    assert_identical("exp", &inputs, branchless::exp, accurate::exp);
+    assert_identical("log", &inputs, branchless::log, accurate::log);
+    assert_identical("sqrt", &inputs, branchless::sqrt, accurate::sqrt);
+    assert_identical("floor", &inputs, branchless::floor, accurate::floor);
+    assert_identical("ceil", &inputs, branchless::ceil, accurate::ceil);
+    assert_identical("fabs", &inputs, branchless::fabs, accurate::fabs);
+    for &other in &[-10.0, -1.0, -0.0, 0.0, 0.5, 3.0, std::f32::INFINITY] {
+        assert_identical("atan2",
+                         &inputs,
+                         |y| branchless::atan2(y, other),
+                         |y| accurate::atan2(y, other));
+        assert_identical("atan2",
+                         &inputs,
+                         |x| branchless::atan2(other, x),
+                         |x| accurate::atan2(other, x));
+        assert_identical("pow",
+                         &inputs,
+                         |x| branchless::pow(x, other),
+                         |x| accurate::pow(x, other));
+        assert_identical("pow",
+                         &inputs,
+                         |y| branchless::pow(other, y),
+                         |y| accurate::pow(other, y));
+    }
+}
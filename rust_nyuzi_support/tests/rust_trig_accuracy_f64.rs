@@ -0,0 +1,86 @@
+// Accuracy of the double precision Rust math port compared to the platform
+// libm, in the same style as rust_trig_accuracy.rs. The thresholds describe
+// the current state of the port, so a test failure means accuracy regressed.
+//
+// Run with `cargo test -- --nocapture` to see the error statistics.
+extern crate nyuzi_support;
+
+mod common;
+
+use common::{linear, geometric, sweep};
+use nyuzi_support::math::f64::{libm, accurate};
+use std::f64::consts::PI;
+
+/// Samples over the whole finite range, evenly spaced on a log scale.
+fn full_range() -> Vec<f64> {
+    let positive = geometric(1e-300, 1.7e308);
+    let negative = positive.iter().map(|&x| -x);
+    positive.iter().cloned().chain(negative).collect()
+}
+
+#[test]
+fn sin() {
+    sweep(&linear(-2.0 * PI, 2.0 * PI), accurate::sin, libm::sin).check("sin", 4, 0.6);
+    sweep(&linear(-1e6, 1e6), accurate::sin, libm::sin).check("sin", 4, 0.6);
+    sweep(&linear(1e6, 1e10), accurate::sin, libm::sin).check("sin", 4, 0.6);
+    sweep(&full_range(), accurate::sin, libm::sin).check("sin", 4, 0.6);
+}
+
+#[test]
+fn cos() {
+    sweep(&linear(-2.0 * PI, 2.0 * PI), accurate::cos, libm::cos).check("cos", 4, 0.6);
+    sweep(&linear(-1e6, 1e6), accurate::cos, libm::cos).check("cos", 4, 0.6);
+    sweep(&linear(1e6, 1e10), accurate::cos, libm::cos).check("cos", 4, 0.6);
+    sweep(&full_range(), accurate::cos, libm::cos).check("cos", 4, 0.6);
+}
+
+#[test]
+fn tan() {
+    sweep(&linear(-1.5, 1.5), accurate::tan, libm::tan).check("tan", 8, 1.0);
+    sweep(&full_range(), accurate::tan, libm::tan).check("tan", 8, 1.0);
+}
+
+#[test]
+fn atan2() {
+    let inputs = linear(-100.0, 100.0);
+    for &x in &[-10.0, -1.0, -0.1, 0.1, 1.0, 10.0] {
+        let name = format!("atan2(y, {})", x);
+        sweep(&inputs, |y| accurate::atan2(y, x), |y| libm::atan2(y, x)).check(&name, 2, 0.6);
+        let name = format!("atan2({}, x)", x);
+        sweep(&inputs, |y| accurate::atan2(x, y), |y| libm::atan2(x, y)).check(&name, 2, 0.6);
+    }
+}
+
+#[test]
+fn exp() {
+    sweep(&linear(-740.0, 709.0), accurate::exp, libm::exp).check("exp", 1, 0.1);
+}
+
+#[test]
+fn log() {
+    sweep(&geometric(1e-310, 1e308), accurate::log, libm::log).check("log", 1, 0.1);
+    sweep(&linear(0.0, 4.0), accurate::log, libm::log).check("log", 1, 0.1);
+}
+
+#[test]
+fn pow() {
+    let bases = linear(-10.0, 10.0);
+    for &y in &[-3.0, -2.5, 0.5, 2.0, 3.0] {
+        let name = format!("pow(x, {})", y);
+        sweep(&bases, |x| accurate::pow(x, y), |x| libm::pow(x, y)).check(&name, 32, 2.0);
+    }
+}
+
+#[test]
+fn sqrt() {
+    sweep(&geometric(5e-324, 1e300), accurate::sqrt, libm::sqrt).check("sqrt", 1, 0.3);
+    sweep(&linear(0.0, 1e4), accurate::sqrt, libm::sqrt).check("sqrt", 1, 0.3);
+}
+
+#[test]
+fn floor_ceil_fabs() {
+    let inputs = linear(-1e6, 1e6);
+    sweep(&inputs, accurate::floor, libm::floor).check("floor", 0, 0.0);
+    sweep(&inputs, accurate::ceil, libm::ceil).check("ceil", 0, 0.0);
+    sweep(&inputs, accurate::fabs, libm::fabs).check("fabs", 0, 0.0);
+}
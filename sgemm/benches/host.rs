@@ -0,0 +1,14 @@
+// Wall-clock times of both variants of every benchmark on the host, see
+// tests/host.rs.
+#![feature(test)]
+extern crate test;
+#[macro_use]
+extern crate nyuzi_support;
+extern crate sgemm;
+
+use sgemm::Sgemm;
+
+host_benches! {
+    naive => Sgemm::naive();
+    blocked => Sgemm::blocked();
+}
@@ -0,0 +1,220 @@
+// Dense single precision matrix multiply, C = A * B for square row-major
+// matrices. Two benchmarks:
+//
+// - `sgemm_naive`: the scalar variant computes one dot product per element of
+//   C, the SPMD variant one element per lane.
+// - `sgemm_blocked`: the scalar variant works through blocks of BLOCK x BLOCK
+//   elements so that they stay in the cache, the SPMD variant computes a tile
+//   of TILE x TILE elements per lane, with the tile in registers.
+//
+// Every variant adds up the products for an element of C in the same order,
+// so they all give exactly the same results, which `verify` checks against
+// the naive scalar version.
+
+#![no_std]
+#![allow(dead_code)]
+#[macro_use]
+extern crate nyuzi_support;
+
+use core::cell::Cell;
+use nyuzi_support::spmd_range;
+use nyuzi_support::benchmark::{Benchmark, Size};
+use nyuzi_support::pool;
+
+// The default size of the matrices
+const N: usize = 64;
+
+// The block size of the blocked scalar variant
+const BLOCK: usize = 16;
+
+// The tile size of the blocked SPMD variant. Matrices are a multiple of 16
+// wide, so there is always a multiple of 16 tiles.
+const TILE: usize = 4;
+
+/// Element (`row`, `col`) of `a * b`.
+fn dot(a: &[f32], b: &[f32], n: usize, row: usize, col: usize) -> f32 {
+    let mut sum = 0.0;
+    for k in 0..n {
+        unsafe {
+            sum += *a.get_unchecked(row * n + k) * *b.get_unchecked(k * n + col);
+        }
+    }
+    sum
+}
+
+fn multiply_naive(a: &[f32], b: &[f32], c: &mut [f32], n: usize) {
+    for row in 0..n {
+        for col in 0..n {
+            c[row * n + col] = dot(a, b, n, row, col);
+        }
+    }
+}
+
+fn multiply_blocked(a: &[f32], b: &[f32], c: &mut [f32], n: usize) {
+    for x in c.iter_mut() {
+        *x = 0.0;
+    }
+    let blocks = n / BLOCK;
+    for row_block in 0..blocks {
+        for col_block in 0..blocks {
+            // In order, so that each element sums up its products in order
+            for k_block in 0..blocks {
+                for row in row_block * BLOCK..(row_block + 1) * BLOCK {
+                    for k in k_block * BLOCK..(k_block + 1) * BLOCK {
+                        let x = a[row * n + k];
+                        for col in col_block * BLOCK..(col_block + 1) * BLOCK {
+                            c[row * n + col] += x * b[k * n + col];
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* tid = get_global_id(0) */
+fn naive_kernel(a: &[f32], b: &[f32], c: &[Cell<f32>], n: usize, tid: usize) {
+    let sum = dot(a, b, n, tid / n, tid % n);
+    unsafe { c.get_unchecked(tid).set(sum) }
+}
+
+/* tid = get_global_id(0) */
+fn tile_kernel(a: &[f32], b: &[f32], c: &[Cell<f32>], n: usize, tid: usize) {
+    let tiles_per_row = n / TILE;
+    let row0 = tid / tiles_per_row * TILE;
+    let col0 = tid % tiles_per_row * TILE;
+
+    let mut acc = [[0.0; TILE]; TILE];
+    for k in 0..n {
+        for i in 0..TILE {
+            let x = unsafe { *a.get_unchecked((row0 + i) * n + k) };
+            for j in 0..TILE {
+                acc[i][j] += x * unsafe { *b.get_unchecked(k * n + col0 + j) };
+            }
+        }
+    }
+
+    for i in 0..TILE {
+        for j in 0..TILE {
+            unsafe { c.get_unchecked((row0 + i) * n + col0 + j).set(acc[i][j]) }
+        }
+    }
+}
+
+fn cells(xs: &mut [f32]) -> &[Cell<f32>] {
+    unsafe { &*(xs as *mut [f32] as *const [Cell<f32>]) }
+}
+
+/// Pseudo-random values in [-1, 1), from a xorshift generator.
+fn fill(xs: &mut [f32], seed: u32) {
+    let mut state = seed;
+    for x in xs {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        *x = (state >> 8) as f32 / (1 << 23) as f32 - 1.0;
+    }
+}
+
+pub struct Sgemm {
+    blocked: bool,
+    n: usize,
+    a: &'static mut [f32],
+    b: &'static mut [f32],
+    c: &'static mut [f32],
+}
+
+impl Sgemm {
+    pub fn naive() -> Self {
+        Sgemm {
+            blocked: false,
+            n: 0,
+            a: &mut [],
+            b: &mut [],
+            c: &mut [],
+        }
+    }
+
+    pub fn blocked() -> Self {
+        Sgemm { blocked: true, ..Sgemm::naive() }
+    }
+}
+
+impl Benchmark for Sgemm {
+    type Output = f32;
+
+    /// `size.n` is the number of rows and columns, a multiple of 16 (the
+    /// default is N). There are no steps.
+    fn setup(&mut self, size: Size) -> bool {
+        let n = if size.n == 0 { N } else { size.n };
+        if n == 0 || n % 16 != 0 || size.steps != 0 {
+            return false;
+        }
+        match (pool::alloc(n * n, 0.0), pool::alloc(n * n, 0.0), pool::alloc(n * n, 0.0)) {
+            (Some(a), Some(b), Some(c)) => {
+                self.a = a;
+                self.b = b;
+                self.c = c;
+            }
+            _ => return false,
+        }
+        self.n = n;
+        fill(self.a, 0x12345678);
+        fill(self.b, 0x9abcdef0);
+        true
+    }
+
+    fn run_scalar(&mut self) {
+        if self.blocked {
+            multiply_blocked(self.a, self.b, self.c, self.n);
+        } else {
+            multiply_naive(self.a, self.b, self.c, self.n);
+        }
+    }
+
+    fn run_spmd(&mut self) {
+        let n = self.n;
+        let (a, b, c) = (&*self.a, &*self.b, cells(self.c));
+        if self.blocked {
+            spmd_range(0..(n / TILE) * (n / TILE), |tid: usize| {
+                tile_kernel(a, b, c, n, tid);
+            });
+        } else {
+            spmd_range(0..n * n, |tid: usize| {
+                naive_kernel(a, b, c, n, tid);
+            });
+        }
+    }
+
+    fn verify(&self) -> bool {
+        let n = self.n;
+        let expected = match pool::alloc(n * n, 0.0) {
+            Some(expected) => expected,
+            None => return false,
+        };
+        multiply_naive(self.a, self.b, expected, n);
+        self.c[..] == expected[..]
+    }
+
+    fn output(&self) -> &[f32] {
+        self.c
+    }
+}
+
+bench! {
+    "sgemm_naive" => static SGEMM_NAIVE: Sgemm = Sgemm {
+        blocked: false,
+        n: 0,
+        a: &mut [],
+        b: &mut [],
+        c: &mut [],
+    };
+
+    "sgemm_blocked" => static SGEMM_BLOCKED: Sgemm = Sgemm {
+        blocked: true,
+        n: 0,
+        a: &mut [],
+        b: &mut [],
+        c: &mut [],
+    };
+}
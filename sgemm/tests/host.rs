@@ -0,0 +1,12 @@
+// Both variants of every benchmark, run on the host through the fallbacks of
+// the SPMD functions.
+#[macro_use]
+extern crate nyuzi_support;
+extern crate sgemm;
+
+use sgemm::Sgemm;
+
+host_tests! {
+    naive => Sgemm::naive(), sizes: [16, 32];
+    blocked => Sgemm::blocked(), sizes: [16, 32];
+}